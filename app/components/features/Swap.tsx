@@ -1,9 +1,9 @@
 'use client';
 
-import { useState } from 'react';
+import { useEffect, useState } from 'react';
 import { useConnection, useAnchorWallet } from '@solana/wallet-adapter-react';
 import { Program, AnchorProvider, BN, Idl } from '@coral-xyz/anchor';
-import { PublicKey } from '@solana/web3.js';
+import { PublicKey, SystemProgram } from '@solana/web3.js';
 import { Button } from '@/components/ui/button'; // Adjust pathes if needed (using @/ alias usually works in Next.js)
 import { Card } from '@/components/ui/card';
 import idl from '@/lib/idl.json'; // Ensure this matches path
@@ -12,6 +12,13 @@ import idl from '@/lib/idl.json'; // Ensure this matches path
 const PROGRAM_ID = new PublicKey("ACvdkCFF3piATdcAXQemmdu5FWXVHfv7kv4Y5vT3jawS");
 const LXR_MINT = new PublicKey("7Qm6qUCXGZfGBYYFzq2kTbwTDah5r3d9DcPJHRT8Wdth");
 const XLS_MINT = new PublicKey("GM4vKHRrqg84mKRixpVr5FuLUNL45b5dFLqcYQQpwoki");
+const SLIPPAGE_BPS = 100; // 1% tolerance on the simulated quote
+
+const [GLOBAL_CONFIG] = PublicKey.findProgramAddressSync([Buffer.from("global_config")], PROGRAM_ID);
+
+type Quote = { lxr: BN; navPerXls: BN };
+
+const formatLxr = (atomic: BN) => (Number(atomic.toString()) / 1_000_000_000).toLocaleString();
 
 export function SwapInterface() {
     const { connection } = useConnection();
@@ -19,6 +26,46 @@ export function SwapInterface() {
     const [amount, setAmount] = useState('');
     const [isLoading, setIsLoading] = useState(false);
     const [mode, setMode] = useState<'buy' | 'redeem'>('buy');
+    const [quote, setQuote] = useState<Quote | null>(null);
+
+    // Prices come from the program's view instructions, simulated against the current state
+    const simulateQuote = async (program: Program, amountAtomic: BN): Promise<Quote> => {
+        const { getAssociatedTokenAddressSync, TOKEN_2022_PROGRAM_ID } = await import('@solana/spl-token');
+        const quoteAccounts = {
+            globalConfig: GLOBAL_CONFIG,
+            xlsMint: XLS_MINT,
+            lxrMint: LXR_MINT,
+            xlsVaultSupply: getAssociatedTokenAddressSync(XLS_MINT, GLOBAL_CONFIG, true, TOKEN_2022_PROGRAM_ID),
+            rwaVaultLxr: getAssociatedTokenAddressSync(LXR_MINT, GLOBAL_CONFIG, true, TOKEN_2022_PROGRAM_ID),
+        };
+        const nav = await program.methods.getNav().accounts(quoteAccounts).view();
+
+        if (mode === 'buy') {
+            const buy = await program.methods.quoteBuy(amountAtomic).accounts(quoteAccounts).view();
+            return { lxr: buy.lxrPaid, navPerXls: nav.navPerXls };
+        }
+        const [userAccountPda] = PublicKey.findProgramAddressSync([Buffer.from("user_account"), wallet!.publicKey.toBuffer()], PROGRAM_ID);
+        const stakerAccount = (await connection.getAccountInfo(userAccountPda)) ? userAccountPda : null;
+        const redeem = await program.methods.quoteRedeem(amountAtomic)
+            .accounts({ ...quoteAccounts, userAccount: stakerAccount }) // Stakers above the threshold pay the reduced fee
+            .view();
+        return { lxr: redeem.lxrOut, navPerXls: nav.navPerXls };
+    };
+
+    useEffect(() => {
+        const amountVal = parseFloat(amount);
+        if (!wallet || !(amountVal > 0)) {
+            setQuote(null);
+            return;
+        }
+        let stale = false;
+        const program = new Program(idl as Idl, PROGRAM_ID, new AnchorProvider(connection, wallet, {}));
+        simulateQuote(program, new BN(amountVal * 1_000_000_000))
+            .then((q) => { if (!stale) setQuote(q); })
+            .catch(() => { if (!stale) setQuote(null); });
+        return () => { stale = true; };
+        // eslint-disable-next-line react-hooks/exhaustive-deps
+    }, [amount, mode, wallet, connection]);
 
     const handleSwap = async () => {
         if (!wallet || !amount) return;
@@ -29,13 +76,16 @@ export function SwapInterface() {
             const program = new Program(idl as Idl, PROGRAM_ID, provider);
 
             // Convert amount to atomic units (Decimals 9)
-            // 1 XLS = 1,000,000 LXR
-            // Input is XLS amount desired?
             const amountVal = parseFloat(amount);
             const amountAtomic = new BN(amountVal * 1_000_000_000);
 
+            // Guaranteed Quote: fresh simulation with 1% slippage tolerance, valid for 60 seconds
+            const { lxr: quoteLxr } = await simulateQuote(program, amountAtomic);
+            const expiresAt = new BN(Math.floor(Date.now() / 1000) + 60);
+
             // Derive PDAs
-            const [globalConfig] = PublicKey.findProgramAddressSync([Buffer.from("global_config")], PROGRAM_ID);
+            const globalConfig = GLOBAL_CONFIG;
+            const [purchaseRecord] = PublicKey.findProgramAddressSync([Buffer.from("purchase_record"), wallet.publicKey.toBuffer()], PROGRAM_ID);
 
             // Get Associated Token Accounts (need to be derived or passed)
             // Ideally we use getAssociatedTokenAddressSync
@@ -49,7 +99,8 @@ export function SwapInterface() {
 
             if (mode === 'buy') {
                 // Buy XLS
-                await program.methods.buyXls(amountAtomic)
+                const maxLxrIn = quoteLxr.muln(10_000 + SLIPPAGE_BPS).divn(10_000);
+                await program.methods.buyXls(amountAtomic, maxLxrIn, expiresAt)
                     .accounts({
                         user: wallet.publicKey,
                         globalConfig,
@@ -59,13 +110,21 @@ export function SwapInterface() {
                         rwaVaultLxr,
                         xlsMint: XLS_MINT,
                         lxrMint: LXR_MINT,
-                        tokenProgram: TOKEN_2022_PROGRAM_ID
+                        tokenProgram: TOKEN_2022_PROGRAM_ID,
+                        purchaseRecord, // Per-wallet cap tracking (created on first buy)
+                        systemProgram: SystemProgram.programId
                     })
                     .rpc();
                 alert("Swap Successful!");
             } else {
                 // Redeem XLS
-                await program.methods.redeemXls(amountAtomic)
+                const config = await program.account.globalConfig.fetch(globalConfig);
+                const [userAccountPda] = PublicKey.findProgramAddressSync([Buffer.from("user_account"), wallet.publicKey.toBuffer()], PROGRAM_ID);
+                const stakerAccount = (await connection.getAccountInfo(userAccountPda)) ? userAccountPda : null;
+
+                // The simulated payout is already net of this wallet's exit fee
+                const minLxrOut = quoteLxr.muln(10_000 - SLIPPAGE_BPS).divn(10_000);
+                await program.methods.redeemXls(amountAtomic, minLxrOut, expiresAt)
                     .accounts({
                         user: wallet.publicKey,
                         globalConfig,
@@ -73,6 +132,8 @@ export function SwapInterface() {
                         userXlsAccount: userXls,
                         xlsVaultSupply,
                         rwaVaultLxr,
+                        lxrVaultRewards: config.lxrVaultRewards, // Receives the exit fee
+                        userAccount: stakerAccount, // Stakers above the threshold pay the reduced fee
                         xlsMint: XLS_MINT,
                         lxrMint: LXR_MINT,
                         tokenProgram: TOKEN_2022_PROGRAM_ID
@@ -122,13 +183,13 @@ export function SwapInterface() {
 
                 <div className="p-3 bg-white/5 rounded-xl space-y-2 text-sm">
                     <div className="flex justify-between">
-                        <span className="text-gray-400">Price</span>
-                        <span className="text-white font-mono">1,000,000 LXR</span>
+                        <span className="text-gray-400">NAV per XLS</span>
+                        <span className="text-white font-mono">{quote ? formatLxr(quote.navPerXls) : '-'} LXR</span>
                     </div>
                     <div className="flex justify-between">
-                        <span className="text-gray-400">Est. Cost</span>
+                        <span className="text-gray-400">{mode === 'buy' ? 'Est. Cost' : 'Est. Proceeds'}</span>
                         <span className="text-amber-500 font-mono">
-                            {amount ? (parseFloat(amount) * 1_000_000).toLocaleString() : '0'} LXR
+                            {quote ? formatLxr(quote.lxr) : '0'} LXR
                         </span>
                     </div>
                 </div>
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{ed25519_program, instruction::Instruction};
+
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+/// `instruction_index` value meaning "data lives in the Ed25519 instruction itself".
+const THIS_INSTRUCTION: u16 = u16::MAX;
+
+fn read_u16(data: &[u8], at: usize) -> Result<u16> {
+    let bytes = data
+        .get(at..at + 2)
+        .ok_or(crate::ErrorCode::InvalidOtcSignature)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn slice(data: &[u8], offset: u16, len: usize) -> Result<&[u8]> {
+    let start = offset as usize;
+    Ok(data
+        .get(start..start + len)
+        .ok_or(crate::ErrorCode::InvalidOtcSignature)?)
+}
+
+/// Checks that `ix` is an Ed25519 program instruction verifying exactly one signature by `signer` over `message`.
+/// The runtime has already rejected the transaction if that signature is invalid, so matching
+/// the key and message is all that remains.
+pub fn verify_ed25519_ix(ix: &Instruction, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    require!(ix.program_id == ed25519_program::ID, crate::ErrorCode::MissingEd25519Instruction);
+    require!(ix.accounts.is_empty(), crate::ErrorCode::InvalidOtcSignature);
+
+    let data = &ix.data;
+    require!(data.first() == Some(&1), crate::ErrorCode::InvalidOtcSignature); // One signature
+
+    let offsets = SIGNATURE_OFFSETS_START;
+    require!(data.len() >= offsets + SIGNATURE_OFFSETS_LEN, crate::ErrorCode::InvalidOtcSignature);
+    let signature_ix = read_u16(data, offsets + 2)?;
+    let pubkey_offset = read_u16(data, offsets + 4)?;
+    let pubkey_ix = read_u16(data, offsets + 6)?;
+    let message_offset = read_u16(data, offsets + 8)?;
+    let message_size = read_u16(data, offsets + 10)?;
+    let message_ix = read_u16(data, offsets + 12)?;
+
+    // Key and message must come from this instruction, not from one the caller controls elsewhere
+    require!(
+        signature_ix == THIS_INSTRUCTION && pubkey_ix == THIS_INSTRUCTION && message_ix == THIS_INSTRUCTION,
+        crate::ErrorCode::InvalidOtcSignature
+    );
+    require!(slice(data, pubkey_offset, PUBKEY_LEN)? == signer.as_ref(), crate::ErrorCode::InvalidOtcSignature);
+    require!(
+        slice(data, message_offset, message_size as usize)? == message,
+        crate::ErrorCode::InvalidOtcSignature
+    );
+    Ok(())
+}
@@ -0,0 +1,425 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+#[event]
+pub struct SwapParamsUpdated {
+    pub old_price: u64,
+    pub new_price: u64,
+    pub old_burn_bps: u16,
+    pub new_burn_bps: u16,
+    pub old_vault_bps: u16,
+    pub new_vault_bps: u16,
+}
+
+#[event]
+pub struct SaleCurveUpdated {
+    pub mode: SaleMode,
+    pub tiers: [SaleTier; MAX_SALE_TIERS],
+    pub total_xls_sold: u64,
+}
+
+#[event]
+pub struct ConfigInitialized {
+    pub admin: Pubkey,
+    pub xls_mint: Pubkey,
+    pub lxr_mint: Pubkey,
+    pub fee_basis_points: u16,
+}
+
+#[event]
+pub struct ConfigUpgraded {
+    pub rwa_vault_lxr: Pubkey,
+    pub xls_vault_supply: Pubkey,
+    pub lxr_vault_rewards: Pubkey,
+    pub xls_vault_staking: Pubkey,
+    pub migrated_xls: u64, // Staked XLS moved out of the supply vault
+}
+
+#[event]
+pub struct RedemptionParamsUpdated {
+    pub old_mode: RedemptionMode,
+    pub new_mode: RedemptionMode,
+    pub old_rate: u64,
+    pub new_rate: u64,
+    pub old_floor: u64,
+    pub new_floor: u64,
+    pub old_ceiling: u64,
+    pub new_ceiling: u64,
+}
+
+#[event]
+pub struct TransferFeeModeUpdated {
+    pub gross_up: bool,
+}
+
+#[event]
+pub struct XlsPurchased {
+    pub buyer: Pubkey,
+    pub amount_xls: u64,
+    pub lxr_paid: u64,
+    pub lxr_burned: u64,
+    pub lxr_to_vault: u64, // Received by the RWA Vault, net of transfer fee
+    pub total_xls_sold: u64,
+}
+
+#[event]
+pub struct XlsRedeemed {
+    pub user: Pubkey,
+    pub amount_xls: u64,
+    pub lxr_out: u64, // Received by the user, net of transfer fee
+    pub mode: RedemptionMode,
+    pub exit_fee: u64, // LXR routed to stakers
+}
+
+#[event]
+pub struct UserInitialized {
+    pub user: Pubkey,
+}
+
+#[event]
+pub struct XlsStaked {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub user_staked: u64,
+    pub total_staked_xls: u64,
+}
+
+#[event]
+pub struct XlsUnstaked {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub user_staked: u64,
+    pub total_staked_xls: u64,
+}
+
+#[event]
+pub struct RewardsPaid {
+    pub user: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64, // Received, net of transfer fee
+    pub total_claimed: u64,
+}
+
+#[event]
+pub struct FeesHarvested {
+    pub harvested: u64,
+    pub founder_share: u64,
+}
+
+#[event]
+pub struct RentDistributed {
+    pub amount: u64,
+    pub rwa_received: u64,
+    pub stakers_received: u64,
+    pub acc_rewards_per_share: u128,
+    pub undistributed_rewards: u64, // Parked until the first stake when nobody is staked
+}
+
+#[event]
+pub struct InflationMinted {
+    pub amount: u64,
+    pub reserve_wallet: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DistributorInitialized {
+    pub root: [u8; 32],
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct MerkleClaimed {
+    pub claimant: Pubkey,
+    pub index: u64,
+    pub amount: u64,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct RedemptionBreakerTripped {
+    pub coverage_bps: u64,
+    pub min_coverage_bps: u16,
+    pub by_admin: bool,
+}
+
+#[event]
+pub struct RedemptionBreakerReset {
+    pub coverage_bps: u64,
+    pub min_coverage_bps: u16,
+    pub by_admin: bool,
+}
+
+#[event]
+pub struct RedemptionBreakerUpdated {
+    pub min_coverage_bps: u16,
+    pub overridden: bool,
+    pub halted: bool,
+}
+
+#[event]
+pub struct RedeemQueueParamsUpdated {
+    pub epoch_duration: i64,
+}
+
+#[event]
+pub struct RedeemRequested {
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub amount_xls: u64,
+    pub ticket_xls: u64,
+}
+
+#[event]
+pub struct RedeemCancelled {
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub amount_xls: u64,
+}
+
+#[event]
+pub struct RedeemEpochSettled {
+    pub epoch: u64,
+    pub xls_requested: u64,
+    pub xls_filled: u64,
+    pub lxr_settled: u64,
+}
+
+#[event]
+pub struct RedeemClaimed {
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub xls_burned: u64,
+    pub xls_returned: u64,
+    pub lxr_received: u64,
+    pub exit_fee: u64,
+}
+
+#[event]
+pub struct PaymentMintUpdated {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub price: u64,
+    pub burn_bps: u16,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct XlsPurchasedWith {
+    pub buyer: Pubkey,
+    pub payment_mint: Pubkey,
+    pub amount_xls: u64,
+    pub amount_paid: u64,
+    pub amount_burned: u64,
+    pub amount_to_vault: u64,
+    pub total_xls_sold: u64,
+}
+
+#[event]
+pub struct LockTiersUpdated {
+    pub tiers: [LockTier; MAX_LOCK_TIERS],
+}
+
+#[event]
+pub struct XlsPurchasedLocked {
+    pub buyer: Pubkey,
+    pub amount_xls: u64,
+    pub lxr_paid: u64,
+    pub discount_bps: u16,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub cliff: bool,
+}
+
+#[event]
+pub struct VestedReleased {
+    pub user: Pubkey,
+    pub amount_xls: u64,
+    pub released_xls: u64,
+    pub total_xls: u64,
+}
+
+#[event]
+pub struct PurchaseCapsUpdated {
+    pub wallet_cap_xls: u64,
+    pub epoch_cap_xls: u64,
+    pub epoch_duration: i64,
+}
+
+#[event]
+pub struct ReferralParamsUpdated {
+    pub referral_bps: u16,
+}
+
+#[event]
+pub struct ReferrerRegistered {
+    pub referrer: Pubkey,
+}
+
+#[event]
+pub struct ReferralCredited {
+    pub referrer: Pubkey,
+    pub buyer: Pubkey,
+    pub amount_xls: u64,
+    pub lxr_credited: u64,
+}
+
+#[event]
+pub struct ReferralClaimed {
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub total_claimed: u64,
+}
+
+#[event]
+pub struct QuoteSignerUpdated {
+    pub quote_signer: Pubkey,
+}
+
+#[event]
+pub struct XlsPurchasedOtc {
+    pub buyer: Pubkey,
+    pub amount_xls: u64,
+    pub price: u64,
+    pub nonce: u64,
+    pub lxr_paid: u64,
+    pub total_xls_sold: u64,
+}
+
+#[event]
+pub struct AuctionCreated {
+    pub id: u64,
+    pub start_price: u64,
+    pub end_price: u64,
+    pub start_time: i64,
+    pub duration: i64,
+    pub tranche_xls: u64,
+}
+
+#[event]
+pub struct XlsPurchasedAuction {
+    pub buyer: Pubkey,
+    pub auction: u64,
+    pub amount_xls: u64,
+    pub price: u64,
+    pub lxr_paid: u64,
+    pub sold_xls: u64,
+}
+
+#[event]
+pub struct AuctionClosed {
+    pub id: u64,
+    pub sold_xls: u64,
+    pub lxr_raised: u64,
+    pub returned_xls: u64,
+}
+
+#[event]
+pub struct AuctionClosedEarly {
+    pub id: u64,
+    pub sold_xls: u64,
+    pub lxr_raised: u64,
+    pub returned_xls: u64,
+    pub ends_at: i64, // Scheduled end the admin closed ahead of
+}
+
+#[event]
+pub struct PoolFeesUpdated {
+    pub fee_bps: u16,
+    pub protocol_fee_bps: u16,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub provider: Pubkey,
+    pub lxr_in: u64,
+    pub xls_in: u64,
+    pub lp_minted: u64,
+    pub lxr_reserve: u64,
+    pub xls_reserve: u64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub provider: Pubkey,
+    pub lxr_out: u64,
+    pub xls_out: u64,
+    pub lp_burned: u64,
+    pub lxr_reserve: u64,
+    pub xls_reserve: u64,
+}
+
+#[event]
+pub struct PoolSwapped {
+    pub user: Pubkey,
+    pub lxr_to_xls: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub protocol_lxr: u64,
+    pub lxr_reserve: u64,
+    pub xls_reserve: u64,
+}
+
+#[event]
+pub struct RedemptionFeeUpdated {
+    pub fee_bps: u16,
+    pub staker_fee_bps: u16,
+    pub staker_threshold_xls: u64,
+}
+
+#[event]
+pub struct EmissionScheduleUpdated {
+    pub rate_per_second: u64,
+    pub funded: u64,
+    pub budget: u64,
+    pub end_ts: i64,
+}
+
+#[event]
+pub struct UndistributedRewardsSwept {
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct StakeLockUpdated {
+    pub user: Pubkey,
+    pub lock_end: i64,
+    pub boost_bps: u16,
+    pub effective_stake: u64,
+    pub total_effective_stake: u64,
+}
+
+#[event]
+pub struct XlsUnbonding {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub unbonding_xls: u64, // User's whole unbonding balance
+    pub unbonding_end: i64,
+}
+
+#[event]
+pub struct UnbondedWithdrawn {
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct UnbondingCancelled {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub user_staked: u64,
+    pub total_staked_xls: u64,
+}
+
+#[event]
+pub struct UnbondingPeriodUpdated {
+    pub unbonding_period: i64,
+}
+
+#[event]
+pub struct UserAccountMigrated {
+    pub user: Pubkey,
+    pub effective_stake: u64,
+}
@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, MintTo};
 use crate::state::*;
+use crate::pricing::*;
+use crate::events::*;
+use crate::math::*;
+use crate::transfer_fee::fee_adjusted;
 
 #[derive(Accounts)]
 pub struct DistributeRent<'info> {
@@ -57,9 +61,16 @@ pub struct TriggerInflation<'info> {
 }
 
 pub fn distribute_rent_handler(ctx: Context<DistributeRent>, amount: u64) -> Result<()> {
+    require!(amount > 0, crate::ErrorCode::ZeroAmount);
+
     // Split: 60% RWA Vault, 40% Stakers (Reward Vault)
-    let rwa_share = amount.checked_mul(60).unwrap().checked_div(100).unwrap();
-    let staker_share = amount.checked_sub(rwa_share).unwrap();
+    let rwa_share = mul_div(amount, 60, 100)?;
+    let staker_share = amount.safe_sub(rwa_share)?;
+
+    // LXR Transfer Fee is withheld on each leg. With gross-up the admin pays it on top.
+    let gross_up = ctx.accounts.global_config.gross_up_transfer_fees;
+    let rwa_transfer = fee_adjusted(&ctx.accounts.lxr_mint, rwa_share, gross_up)?;
+    let staker_transfer = fee_adjusted(&ctx.accounts.lxr_mint, staker_share, gross_up)?;
 
     // 1. Transfer 60% to RWA Vault
     if rwa_share > 0 {
@@ -71,7 +82,7 @@ pub fn distribute_rent_handler(ctx: Context<DistributeRent>, amount: u64) -> Res
         };
         token_interface::transfer_checked(
             CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-            rwa_share,
+            rwa_transfer.send,
             ctx.accounts.lxr_mint.decimals,
         )?;
     }
@@ -86,24 +97,28 @@ pub fn distribute_rent_handler(ctx: Context<DistributeRent>, amount: u64) -> Res
         };
         token_interface::transfer_checked(
             CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-            staker_share,
+            staker_transfer.send,
             ctx.accounts.lxr_mint.decimals,
         )?;
 
         // 3. Update Global Config Acc Rewards Per Share
-        // acc_rewards += (reward_amount * 1e12) / total_staked_xls
-        let total_staked = ctx.accounts.global_config.total_staked_xls;
-        if total_staked > 0 {
-            let additional_acc = (staker_share as u128)
-                .checked_mul(1_000_000_000_000).unwrap()
-                .checked_div(total_staked as u128).unwrap();
-            
-            ctx.accounts.global_config.acc_rewards_per_share = 
-                ctx.accounts.global_config.acc_rewards_per_share.checked_add(additional_acc).unwrap();
-        }
+        // Only what the Reward Vault actually received is owed to stakers.
+        // With nobody staked it is parked until the first stake instead of being stranded.
+        credit_stakers(&mut ctx.accounts.global_config, staker_transfer.received)?;
     }
 
-    msg!("Rent Distributed: {} LXR (60% RWA, 40% Stakers)", amount);
+    emit!(RentDistributed {
+        amount,
+        rwa_received: rwa_transfer.received,
+        stakers_received: staker_transfer.received,
+        acc_rewards_per_share: ctx.accounts.global_config.acc_rewards_per_share,
+        undistributed_rewards: ctx.accounts.global_config.undistributed_rewards,
+    });
+
+    msg!(
+        "Rent Distributed: {} LXR (RWA Received {}, Stakers Received {})",
+        amount, rwa_transfer.received, staker_transfer.received
+    );
     Ok(())
 }
 
@@ -118,11 +133,11 @@ pub fn trigger_inflation_handler(ctx: Context<TriggerInflation>) -> Result<()> {
     // Bypass check if last_time is 0 (First run needs initialization? Or we set it at init?)
     // Assuming initialized at launch. If user wants to force trigger for testing, we might need a debug flag.
     // Standard rule:
-    require!(current_time >= last_time + FIVE_YEARS, crate::ErrorCode::InflationNotReady); // We need to define ErrorCode
+    require!(current_time >= last_time.safe_add(FIVE_YEARS)?, crate::ErrorCode::InflationNotReady);
 
     // Mint 2.5% of Current Supply
     let current_supply = ctx.accounts.lxr_mint.supply;
-    let mint_amount = current_supply.checked_mul(25).unwrap().checked_div(1000).unwrap(); // 2.5%
+    let mint_amount = mul_div(current_supply, 25, 1000)?; // 2.5%
 
     // Execute Mint
     // Admin holds Mint Authority (Retained), so we use Admin as signer/authority
@@ -140,46 +155,863 @@ pub fn trigger_inflation_handler(ctx: Context<TriggerInflation>) -> Result<()> {
     // Update Timestamp
     ctx.accounts.global_config.last_inflation_timestamp = current_time;
 
+    emit!(InflationMinted {
+        amount: mint_amount,
+        reserve_wallet: ctx.accounts.reserve_wallet.key(),
+        timestamp: current_time,
+    });
+
     msg!("Inflation Triggered: Minted {} LXR to Reserve", mint_amount);
     Ok(())
 }
 #[derive(Accounts)]
 pub struct UpgradeConfig<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub admin: Signer<'info>, // Checked against the config in the handler; pays for the realloc
 
+    /// CHECK: May still have the baseline layout, which Account<GlobalConfig> cannot deserialize;
+    /// PDA and owner are checked here, the discriminator and admin in the handler
     #[account(
         mut,
         seeds = [b"global_config"],
-        bump = global_config.bump,
-        has_one = admin,
-        realloc = GlobalConfig::LEN,
-        realloc::payer = admin,
-        realloc::zero = false,
+        bump,
+        owner = crate::ID,
     )]
-    pub global_config: Account<'info, GlobalConfig>,
+    pub global_config: UncheckedAccount<'info>,
 
     // New Fields to Set
     /// CHECK: RWA Vault (LXR)
     pub rwa_vault_lxr: UncheckedAccount<'info>,
-    /// CHECK: XLS Supply Vault
+    /// CHECK: XLS Supply Vault. Source of the move out of the baseline layout, where the token program checks it.
+    #[account(mut)]
     pub xls_vault_supply: UncheckedAccount<'info>,
     /// CHECK: LXR Reward Vault
     pub lxr_vault_rewards: UncheckedAccount<'info>,
+    // Must not be the supply vault, or staked XLS would be sold
+    #[account(
+        mut,
+        token::mint = xls_mint,
+        token::authority = global_config,
+        constraint = xls_vault_staking.key() != xls_vault_supply.key() @ crate::ErrorCode::SharedStakingVault,
+    )]
+    pub xls_vault_staking: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>, // Checked against the config in the handler
+    #[account(address = anchor_spl::token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
 
     pub system_program: Program<'info, System>,
 }
 
+/// Staked and unbonding XLS that `upgrade_config` must move from the supply vault into the staking vault.
+/// Baseline configs had no staking vault and kept stakes in the supply vault. Once a staking vault is set,
+/// it cannot be swapped out while it still holds XLS for stakers.
+pub(crate) fn stake_to_migrate(config: &GlobalConfig, new_staking_vault: &Pubkey) -> Result<u64> {
+    let held_for_stakers = config.total_staked_xls.safe_add(config.total_unbonding_xls)?;
+    if config.is_baseline_layout() {
+        return Ok(held_for_stakers);
+    }
+    require!(
+        *new_staking_vault == config.xls_vault_staking || held_for_stakers == 0,
+        crate::ErrorCode::StakingVaultInUse
+    );
+    Ok(0)
+}
+
 pub fn upgrade_config_handler(ctx: Context<UpgradeConfig>) -> Result<()> {
-    let config = &mut ctx.accounts.global_config;
+    // Grow the account to the current layout; the appended fields start zeroed
+    let info = ctx.accounts.global_config.to_account_info();
+    if info.data_len() < GlobalConfig::LEN {
+        let required = Rent::get()?.minimum_balance(GlobalConfig::LEN);
+        let top_up = required.saturating_sub(info.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        info.realloc(GlobalConfig::LEN, true)?;
+    }
+
+    let mut config = GlobalConfig::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    require_keys_eq!(config.admin, ctx.accounts.admin.key(), anchor_lang::error::ErrorCode::ConstraintHasOne);
+    require_keys_eq!(ctx.accounts.xls_mint.key(), config.xls_mint, crate::ErrorCode::InvalidXlsMint);
+
+    // Baseline configs kept staked XLS in the supply vault: move it to the new staking vault
+    let baseline = config.is_baseline_layout();
+    let migrated_xls = stake_to_migrate(&config, &ctx.accounts.xls_vault_staking.key())?;
+    if migrated_xls > 0 {
+        require_keys_eq!(
+            ctx.accounts.xls_vault_supply.key(),
+            config.xls_vault_supply,
+            crate::ErrorCode::InvalidSupplyVault
+        );
+
+        let seeds = &[b"global_config".as_ref(), &[config.bump]];
+        let signer = &[&seeds[..]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.xls_vault_supply.to_account_info(),
+                    mint: ctx.accounts.xls_mint.to_account_info(),
+                    to: ctx.accounts.xls_vault_staking.to_account_info(),
+                    authority: info.clone(),
+                },
+                signer,
+            ),
+            migrated_xls,
+            ctx.accounts.xls_mint.decimals,
+        )?;
+    }
+
     let clock = Clock::get()?;
 
     // Set new fields
     config.rwa_vault_lxr = ctx.accounts.rwa_vault_lxr.key();
     config.xls_vault_supply = ctx.accounts.xls_vault_supply.key();
     config.lxr_vault_rewards = ctx.accounts.lxr_vault_rewards.key();
+    config.xls_vault_staking = ctx.accounts.xls_vault_staking.key();
     config.last_inflation_timestamp = clock.unix_timestamp;
 
+    // Fields appended to the baseline layout are zeroed by realloc: default to Book Value redemption.
+    // Later upgrades leave them alone, since admins may have changed them since.
+    if baseline {
+        config.redemption_mode = RedemptionMode::Fixed;
+        config.redemption_rate = DEFAULT_REDEMPTION_RATE;
+        config.swap_price = DEFAULT_SWAP_PRICE;
+        config.swap_burn_bps = DEFAULT_SWAP_BURN_BPS;
+        config.swap_vault_bps = DEFAULT_SWAP_VAULT_BPS;
+        config.redeem_epoch_duration = DEFAULT_REDEEM_EPOCH_DURATION;
+        // Stakes predating lock boosts all count at 1x (matches migrate_user_account)
+        config.total_effective_stake = config.total_staked_xls;
+    }
+
+    emit!(ConfigUpgraded {
+        rwa_vault_lxr: config.rwa_vault_lxr,
+        xls_vault_supply: config.xls_vault_supply,
+        lxr_vault_rewards: config.lxr_vault_rewards,
+        xls_vault_staking: config.xls_vault_staking,
+        migrated_xls,
+    });
+
+    config.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
     msg!("Global Config Upgraded to V3 (Size Increased)");
     Ok(())
 }
+
+#[derive(Accounts)]
+pub struct SetRedemptionParams<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RedemptionParams {
+    pub mode: RedemptionMode,
+    pub rate: u64,    // Book Value, LXR per XLS: paid in Fixed Mode, the breaker's liability in both modes
+    pub floor: u64,   // NAV Mode: Min LXR per XLS
+    pub ceiling: u64, // NAV Mode: Max LXR per XLS (0 = No Cap)
+}
+
+pub fn set_redemption_params_handler(ctx: Context<SetRedemptionParams>, params: RedemptionParams) -> Result<()> {
+    require!(
+        params.rate > 0, // A zero book value would read as unlimited coverage and disable the breaker
+        crate::ErrorCode::InvalidRedemptionParams
+    );
+    require!(
+        params.ceiling == 0 || params.floor <= params.ceiling,
+        crate::ErrorCode::InvalidRedemptionParams
+    );
+
+    let config = &mut ctx.accounts.global_config;
+    emit!(RedemptionParamsUpdated {
+        old_mode: config.redemption_mode,
+        new_mode: params.mode,
+        old_rate: config.redemption_rate,
+        new_rate: params.rate,
+        old_floor: config.redemption_floor,
+        new_floor: params.floor,
+        old_ceiling: config.redemption_ceiling,
+        new_ceiling: params.ceiling,
+    });
+
+    config.redemption_mode = params.mode;
+    config.redemption_rate = params.rate;
+    config.redemption_floor = params.floor;
+    config.redemption_ceiling = params.ceiling;
+
+    msg!(
+        "Redemption Params Updated: Rate {}, Floor {}, Ceiling {}",
+        params.rate, params.floor, params.ceiling
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetSwapParams<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SwapParams {
+    pub price: u64,     // LXR per XLS
+    pub burn_bps: u16,  // Share of LXR burned
+    pub vault_bps: u16, // Share of LXR sent to RWA Vault
+}
+
+pub fn set_swap_params_handler(ctx: Context<SetSwapParams>, params: SwapParams) -> Result<()> {
+    require!(params.price > 0, crate::ErrorCode::InvalidSwapParams);
+    require!(
+        (params.burn_bps as u64).safe_add(params.vault_bps as u64)? == BPS_DENOMINATOR,
+        crate::ErrorCode::InvalidSwapParams
+    );
+    require!(params.burn_bps <= MAX_SWAP_BURN_BPS, crate::ErrorCode::BurnBpsAboveCap);
+
+    let config = &mut ctx.accounts.global_config;
+    emit!(SwapParamsUpdated {
+        old_price: config.swap_price,
+        new_price: params.price,
+        old_burn_bps: config.swap_burn_bps,
+        new_burn_bps: params.burn_bps,
+        old_vault_bps: config.swap_vault_bps,
+        new_vault_bps: params.vault_bps,
+    });
+
+    config.swap_price = params.price;
+    config.swap_burn_bps = params.burn_bps;
+    config.swap_vault_bps = params.vault_bps;
+
+    msg!(
+        "Swap Params Updated: Price {} LXR, Burn {} bps, Vault {} bps",
+        params.price, params.burn_bps, params.vault_bps
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetSaleCurve<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SaleCurveParams {
+    pub mode: SaleMode,
+    pub tiers: [SaleTier; MAX_SALE_TIERS], // Only read in Tiered Mode
+}
+
+pub fn set_sale_curve_handler(ctx: Context<SetSaleCurve>, params: SaleCurveParams) -> Result<()> {
+    if params.mode == SaleMode::Tiered {
+        require!(validate_sale_tiers(&params.tiers), crate::ErrorCode::InvalidSaleCurve);
+    }
+
+    let config = &mut ctx.accounts.global_config;
+    config.sale_mode = params.mode;
+    config.sale_tiers = params.tiers;
+
+    emit!(SaleCurveUpdated {
+        mode: config.sale_mode,
+        tiers: config.sale_tiers,
+        total_xls_sold: config.total_xls_sold,
+    });
+
+    msg!("Sale Curve Updated. Sold so far: {} XLS", config.total_xls_sold);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetTransferFeeMode<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+pub fn set_transfer_fee_mode_handler(ctx: Context<SetTransferFeeMode>, gross_up: bool) -> Result<()> {
+    ctx.accounts.global_config.gross_up_transfer_fees = gross_up;
+
+    emit!(TransferFeeModeUpdated { gross_up });
+
+    msg!("LXR Transfer Fee Gross-Up: {}", gross_up);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetRedeemQueueParams<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+pub fn set_redeem_queue_params_handler(ctx: Context<SetRedeemQueueParams>, epoch_duration: i64) -> Result<()> {
+    require!(epoch_duration > 0, crate::ErrorCode::InvalidRedeemQueueParams);
+
+    ctx.accounts.global_config.redeem_epoch_duration = epoch_duration;
+
+    emit!(RedeemQueueParamsUpdated { epoch_duration });
+
+    msg!("Redemption Epoch Duration: {}s", epoch_duration);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetPaymentMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"payment_mint", payment_mint.key().as_ref()],
+        bump,
+        space = PaymentMint::LEN
+    )]
+    pub payment_config: Account<'info, PaymentMint>,
+
+    #[account(
+        constraint = payment_mint.key() != global_config.xls_mint @ crate::ErrorCode::InvalidPaymentMint,
+        constraint = *payment_mint.to_account_info().owner == payment_token_program.key() @ crate::ErrorCode::InvalidTokenProgram,
+    )]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        constraint = payment_vault.mint == payment_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+    )] // Treasury account receiving the retained share
+    pub payment_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub payment_token_program: Interface<'info, TokenInterface>, // SPL Token or Token-2022
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PaymentMintParams {
+    pub price: u64,    // Payment-mint units per XLS
+    pub burn_bps: u16, // Share of the payment burned
+    pub enabled: bool,
+}
+
+pub fn set_payment_mint_handler(ctx: Context<SetPaymentMint>, params: PaymentMintParams) -> Result<()> {
+    require!(params.price > 0, crate::ErrorCode::InvalidPaymentParams);
+    require!(params.burn_bps <= MAX_SWAP_BURN_BPS, crate::ErrorCode::BurnBpsAboveCap);
+
+    let payment_config = &mut ctx.accounts.payment_config;
+    payment_config.mint = ctx.accounts.payment_mint.key();
+    payment_config.token_program = ctx.accounts.payment_token_program.key();
+    payment_config.vault = ctx.accounts.payment_vault.key();
+    payment_config.price = params.price;
+    payment_config.burn_bps = params.burn_bps;
+    payment_config.enabled = params.enabled;
+    payment_config.bump = ctx.bumps.payment_config;
+
+    emit!(PaymentMintUpdated {
+        mint: payment_config.mint,
+        vault: payment_config.vault,
+        price: params.price,
+        burn_bps: params.burn_bps,
+        enabled: params.enabled,
+    });
+
+    msg!(
+        "Payment Mint {}: Price {}, Burn {} bps, Enabled {}",
+        payment_config.mint, params.price, params.burn_bps, params.enabled
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetLockTiers<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LockTierParams {
+    pub tiers: [LockTier; MAX_LOCK_TIERS], // All zero disables locked purchases
+}
+
+pub fn set_lock_tiers_handler(ctx: Context<SetLockTiers>, params: LockTierParams) -> Result<()> {
+    let tiers = params.tiers;
+    require!(validate_lock_tiers(&tiers), crate::ErrorCode::InvalidLockTiers);
+
+    ctx.accounts.global_config.lock_tiers = tiers;
+
+    emit!(LockTiersUpdated { tiers });
+
+    msg!("Lock Tiers Updated: {} active", tiers.iter().filter(|t| t.duration > 0).count());
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetPurchaseCaps<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PurchaseCapParams {
+    pub wallet_cap_xls: u64, // 0 = No Cap
+    pub epoch_cap_xls: u64,  // 0 = No Cap
+    pub epoch_duration: i64, // Seconds per sale epoch
+}
+
+pub fn set_purchase_caps_handler(ctx: Context<SetPurchaseCaps>, params: PurchaseCapParams) -> Result<()> {
+    require!(
+        params.epoch_duration >= 0 && (params.epoch_cap_xls == 0 || params.epoch_duration > 0),
+        crate::ErrorCode::InvalidPurchaseCaps
+    );
+
+    let config = &mut ctx.accounts.global_config;
+    config.wallet_cap_xls = params.wallet_cap_xls;
+    config.epoch_cap_xls = params.epoch_cap_xls;
+
+    // A new window length starts a fresh epoch
+    if config.sale_epoch_duration != params.epoch_duration {
+        config.sale_epoch_duration = params.epoch_duration;
+        config.sale_epoch_start = Clock::get()?.unix_timestamp;
+        config.sale_epoch_sold = 0;
+    }
+
+    emit!(PurchaseCapsUpdated {
+        wallet_cap_xls: config.wallet_cap_xls,
+        epoch_cap_xls: config.epoch_cap_xls,
+        epoch_duration: config.sale_epoch_duration,
+    });
+
+    msg!(
+        "Purchase Caps: {} XLS per wallet, {} XLS per {}s epoch",
+        config.wallet_cap_xls, config.epoch_cap_xls, config.sale_epoch_duration
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetReferralParams<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+pub fn set_referral_params_handler(ctx: Context<SetReferralParams>, referral_bps: u16) -> Result<()> {
+    require!(referral_bps <= MAX_REFERRAL_BPS, crate::ErrorCode::InvalidReferralParams);
+
+    ctx.accounts.global_config.referral_bps = referral_bps;
+
+    emit!(ReferralParamsUpdated { referral_bps });
+
+    msg!("Referral Share: {} bps of the vault leg", referral_bps);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetQuoteSigner<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+/// `Pubkey::default()` disables OTC fills.
+pub fn set_quote_signer_handler(ctx: Context<SetQuoteSigner>, quote_signer: Pubkey) -> Result<()> {
+    ctx.accounts.global_config.quote_signer = quote_signer;
+
+    emit!(QuoteSignerUpdated { quote_signer });
+
+    msg!("OTC Quote Signer: {}", quote_signer);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetRedemptionFee<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RedemptionFeeParams {
+    pub fee_bps: u16,
+    pub staker_fee_bps: u16, // Must not exceed fee_bps
+    pub staker_threshold_xls: u64, // Minimum stake for the reduced tier (0 = No reduced tier)
+}
+
+pub fn set_redemption_fee_handler(ctx: Context<SetRedemptionFee>, params: RedemptionFeeParams) -> Result<()> {
+    require!(
+        params.fee_bps <= MAX_REDEMPTION_FEE_BPS && params.staker_fee_bps <= params.fee_bps,
+        crate::ErrorCode::InvalidRedemptionFee
+    );
+
+    let config = &mut ctx.accounts.global_config;
+    config.redemption_fee_bps = params.fee_bps;
+    config.staker_redemption_fee_bps = params.staker_fee_bps;
+    config.staker_fee_threshold_xls = params.staker_threshold_xls;
+
+    emit!(RedemptionFeeUpdated {
+        fee_bps: params.fee_bps,
+        staker_fee_bps: params.staker_fee_bps,
+        staker_threshold_xls: params.staker_threshold_xls,
+    });
+
+    msg!(
+        "Redemption Exit Fee: {} bps ({} bps when staking >= {} XLS)",
+        params.fee_bps, params.staker_fee_bps, params.staker_threshold_xls
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetEmissionSchedule<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        constraint = admin_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = admin_lxr_account.owner == admin.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub admin_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>, // Source of the funding
+
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+    )]
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = anchor_spl::token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct EmissionParams {
+    pub rate_per_second: u64, // 0 stops emission; the unspent budget stays in the Reward Vault
+    pub fund_amount: u64, // LXR added to the budget (may be 0 to only change the rate)
+}
+
+/// Settles emission at the old rate, tops up the budget and restarts the schedule so it ends when the budget is spent.
+pub fn set_emission_schedule_handler(ctx: Context<SetEmissionSchedule>, params: EmissionParams) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    accrue_emissions(&mut ctx.accounts.global_config, now)?;
+
+    // Only what the Reward Vault actually received can be emitted
+    let mut funded = 0;
+    if params.fund_amount > 0 {
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.admin_lxr_account.to_account_info(),
+            mint: ctx.accounts.lxr_mint.to_account_info(),
+            to: ctx.accounts.lxr_vault_rewards.to_account_info(),
+            authority: ctx.accounts.admin.to_account_info(),
+        };
+        token_interface::transfer_checked(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            params.fund_amount,
+            ctx.accounts.lxr_mint.decimals,
+        )?;
+        funded = fee_adjusted(&ctx.accounts.lxr_mint, params.fund_amount, false)?.received;
+    }
+
+    let config = &mut ctx.accounts.global_config;
+    config.emission_budget = config.emission_budget.safe_add(funded)?;
+    config.reward_rate_per_second = params.rate_per_second;
+    config.emission_end_ts = if params.rate_per_second == 0 {
+        now
+    } else {
+        let duration = config.emission_budget.div_ceil(params.rate_per_second);
+        now.safe_add(i64::try_from(duration).map_err(|_| error!(crate::ErrorCode::MathOverflow))?)?
+    };
+
+    emit!(EmissionScheduleUpdated {
+        rate_per_second: config.reward_rate_per_second,
+        funded,
+        budget: config.emission_budget,
+        end_ts: config.emission_end_ts,
+    });
+
+    msg!(
+        "Emission: {} LXR/s, budget {} LXR, ends at {}",
+        config.reward_rate_per_second, config.emission_budget, config.emission_end_ts
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SweepUndistributedRewards<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+    )]
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+    )]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>, // e.g. the RWA Vault to redirect, or a treasury account
+
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = anchor_spl::token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Moves parked rewards out of the Reward Vault. The destination bears the LXR transfer fee.
+pub fn sweep_undistributed_rewards_handler(ctx: Context<SweepUndistributedRewards>, amount: u64) -> Result<()> {
+    require!(amount > 0, crate::ErrorCode::ZeroAmount);
+    require!(amount <= ctx.accounts.global_config.undistributed_rewards, crate::ErrorCode::InsufficientFunds);
+
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.lxr_vault_rewards.to_account_info(),
+        mint: ctx.accounts.lxr_mint.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+        authority: ctx.accounts.global_config.to_account_info(),
+    };
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer),
+        amount,
+        ctx.accounts.lxr_mint.decimals,
+    )?;
+
+    let config = &mut ctx.accounts.global_config;
+    config.undistributed_rewards = config.undistributed_rewards.safe_sub(amount)?;
+
+    emit!(UndistributedRewardsSwept {
+        destination: ctx.accounts.destination.key(),
+        amount,
+        remaining: config.undistributed_rewards,
+    });
+
+    msg!("Swept {} undistributed LXR, {} remaining", amount, config.undistributed_rewards);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetUnbondingPeriod<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+/// Applies to later unstakes only; balances already unbonding keep their end time.
+pub fn set_unbonding_period_handler(ctx: Context<SetUnbondingPeriod>, unbonding_period: i64) -> Result<()> {
+    require!(
+        (0..=MAX_UNBONDING_PERIOD).contains(&unbonding_period),
+        crate::ErrorCode::InvalidUnbondingPeriod
+    );
+
+    ctx.accounts.global_config.unbonding_period = unbonding_period;
+
+    emit!(UnbondingPeriodUpdated { unbonding_period });
+
+    msg!("Unbonding Period: {}s", unbonding_period);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    /// A config as written by the baseline program, zero-padded the way `upgrade_config` reallocs it.
+    fn baseline_config_bytes(total_staked_xls: u64) -> Vec<u8> {
+        let mut data = GlobalConfig::DISCRIMINATOR.to_vec();
+        for _ in 0..7 {
+            data.extend_from_slice(Pubkey::new_unique().as_ref()); // admin .. founder_wallet
+        }
+        data.extend_from_slice(&100u16.to_le_bytes()); // fee_basis_points
+        data.extend_from_slice(&1_000u16.to_le_bytes()); // max_fee_basis_points
+        data.extend_from_slice(&0u64.to_le_bytes()); // total_lxr_burned
+        data.extend_from_slice(&total_staked_xls.to_le_bytes());
+        data.extend_from_slice(&0u128.to_le_bytes()); // acc_rewards_per_share
+        data.extend_from_slice(&0i64.to_le_bytes()); // last_inflation_timestamp
+        data.extend_from_slice(Pubkey::new_unique().as_ref()); // lxr_vault_rewards
+        data.push(255); // bump
+        assert_eq!(data.len(), 8 + 32*8 + 2*2 + 8*2 + 8 + 16 + 1);
+
+        data.resize(GlobalConfig::LEN, 0);
+        data
+    }
+
+    #[test]
+    fn baseline_upgrade_moves_staked_xls_out_of_the_supply_vault() {
+        let data = baseline_config_bytes(5_000);
+        let config = GlobalConfig::try_deserialize(&mut &data[..]).unwrap();
+
+        assert!(config.is_baseline_layout());
+        assert_eq!(config.total_staked_xls, 5_000);
+        assert_eq!(stake_to_migrate(&config, &Pubkey::new_unique()).unwrap(), 5_000);
+    }
+
+    #[test]
+    fn staking_vault_cannot_be_repointed_while_it_holds_stake() {
+        let vault = Pubkey::new_unique();
+        let mut config = GlobalConfig { xls_vault_staking: vault, total_unbonding_xls: 1, ..Default::default() };
+        assert!(!config.is_baseline_layout());
+
+        // Re-running the upgrade with the same vault moves nothing
+        assert_eq!(stake_to_migrate(&config, &vault).unwrap(), 0);
+        assert_eq!(
+            stake_to_migrate(&config, &Pubkey::new_unique()).unwrap_err(),
+            crate::ErrorCode::StakingVaultInUse.into()
+        );
+
+        // An empty vault can be replaced
+        config.total_unbonding_xls = 0;
+        assert_eq!(stake_to_migrate(&config, &Pubkey::new_unique()).unwrap(), 0);
+    }
+
+    #[test]
+    fn emission_accrues_lazily_until_budget_or_end() {
+        let mut config = GlobalConfig {
+            reward_rate_per_second: 10,
+            last_update_ts: 100,
+            emission_end_ts: 200,
+            emission_budget: 250,
+            total_effective_stake: 5,
+            ..Default::default()
+        };
+
+        accrue_emissions(&mut config, 110).unwrap();
+        assert_eq!(config.emission_budget, 150);
+        assert_eq!(config.acc_rewards_per_share, 100 / 5 * REWARD_PRECISION);
+
+        // Nobody staked: time passes, budget is kept
+        config.total_effective_stake = 0;
+        accrue_emissions(&mut config, 120).unwrap();
+        assert_eq!((config.last_update_ts, config.emission_budget), (120, 150));
+
+        // Budget runs out before the end time
+        config.total_effective_stake = 5;
+        accrue_emissions(&mut config, 190).unwrap();
+        assert_eq!(config.emission_budget, 0);
+        assert_eq!(config.acc_rewards_per_share, 250 / 5 * REWARD_PRECISION);
+
+        // Nothing accrues past the end
+        config.emission_budget = 1_000;
+        accrue_emissions(&mut config, 300).unwrap();
+        assert_eq!(config.emission_budget, 900);
+    }
+
+    #[test]
+    fn rewards_park_while_nobody_is_staked() {
+        let mut config = GlobalConfig::default();
+
+        credit_stakers(&mut config, 300).unwrap();
+        assert_eq!((config.undistributed_rewards, config.acc_rewards_per_share), (300, 0));
+
+        // The first credit with stakers present releases the parked balance
+        config.total_effective_stake = 10;
+        credit_stakers(&mut config, 0).unwrap();
+        assert_eq!(config.undistributed_rewards, 0);
+        assert_eq!(config.acc_rewards_per_share, 300 / 10 * REWARD_PRECISION);
+
+        credit_stakers(&mut config, 100).unwrap();
+        assert_eq!(config.acc_rewards_per_share, 400 / 10 * REWARD_PRECISION);
+    }
+}
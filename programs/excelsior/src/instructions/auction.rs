@@ -0,0 +1,412 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, Burn, CloseAccount};
+use crate::state::*;
+use crate::pricing;
+use crate::transfer_fee::fee_adjusted;
+use crate::math::SafeMath;
+use crate::events::*;
+
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"auction", global_config.next_auction_id.to_le_bytes().as_ref()],
+        bump,
+        space = Auction::LEN
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"auction_vault", auction.key().as_ref()],
+        bump,
+        token::mint = xls_mint,
+        token::authority = global_config,
+        token::token_program = token_program,
+    )] // Tranche being auctioned
+    pub auction_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault,
+        constraint = xls_vault_supply.mint == xls_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = xls_vault_supply.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyXlsAuction<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"auction", auction.id.to_le_bytes().as_ref()],
+        bump = auction.bump,
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    #[account(
+        mut,
+        seeds = [b"auction_vault", auction.key().as_ref()],
+        bump,
+    )]
+    pub auction_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"purchase_record", user.key().as_ref()],
+        bump,
+        space = PurchaseRecord::LEN
+    )]
+    pub purchase_record: Box<Account<'info, PurchaseRecord>>,
+
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault,
+        constraint = rwa_vault_lxr.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = rwa_vault_lxr.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAuction<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"auction", auction.id.to_le_bytes().as_ref()],
+        bump = auction.bump,
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    #[account(
+        mut,
+        seeds = [b"auction_vault", auction.key().as_ref()],
+        bump,
+    )]
+    pub auction_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault,
+    )]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AuctionParams {
+    pub start_price: u64, // LXR per XLS
+    pub end_price: u64, // LXR per XLS (Floor, <= start_price)
+    pub start_time: i64,
+    pub duration: i64, // Seconds
+    pub tranche_xls: u64,
+}
+
+pub fn create_auction_handler(ctx: Context<CreateAuction>, params: AuctionParams) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        params.end_price > 0
+            && params.start_price >= params.end_price
+            && params.duration > 0
+            && params.tranche_xls > 0
+            && params.start_time.safe_add(params.duration)? > now,
+        crate::ErrorCode::InvalidAuctionParams
+    );
+    require!(ctx.accounts.xls_vault_supply.amount >= params.tranche_xls, crate::ErrorCode::VaultInsufficient);
+
+    // Move the tranche out of the Supply Vault so other sale paths cannot oversell it
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let transfer_xls = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.xls_vault_supply.to_account_info(),
+            mint: ctx.accounts.xls_mint.to_account_info(),
+            to: ctx.accounts.auction_vault.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer_xls, params.tranche_xls, ctx.accounts.xls_mint.decimals)?;
+
+    let config = &mut ctx.accounts.global_config;
+    let auction = &mut ctx.accounts.auction;
+    auction.id = config.next_auction_id;
+    auction.start_price = params.start_price;
+    auction.end_price = params.end_price;
+    auction.start_time = params.start_time;
+    auction.duration = params.duration;
+    auction.tranche_xls = params.tranche_xls;
+    auction.sold_xls = 0;
+    auction.lxr_raised = 0;
+    auction.bump = ctx.bumps.auction;
+
+    config.next_auction_id = config.next_auction_id.safe_add(1)?;
+    config.auction_xls_escrowed = config.auction_xls_escrowed.safe_add(params.tranche_xls)?;
+
+    emit!(AuctionCreated {
+        id: auction.id,
+        start_price: auction.start_price,
+        end_price: auction.end_price,
+        start_time: auction.start_time,
+        duration: auction.duration,
+        tranche_xls: auction.tranche_xls,
+    });
+
+    msg!(
+        "Auction {}: {} XLS from {} to {} LXR over {}s",
+        auction.id, auction.tranche_xls, auction.start_price, auction.end_price, auction.duration
+    );
+    Ok(())
+}
+
+pub fn buy_auction_handler(ctx: Context<BuyXlsAuction>, amount_xls: u64, max_lxr_in: u64, expires_at: i64) -> Result<()> {
+    require!(amount_xls > 0, crate::ErrorCode::ZeroAmount);
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= expires_at, crate::ErrorCode::QuoteExpired);
+    require!(ctx.accounts.auction_vault.amount >= amount_xls, crate::ErrorCode::VaultInsufficient);
+
+    let price = pricing::auction_price(&ctx.accounts.auction, now)?;
+    let lxr_needed = pricing::quote_buy_with(
+        price,
+        amount_xls,
+        ctx.accounts.xls_mint.decimals,
+        ctx.accounts.lxr_mint.decimals,
+    )?;
+    let (burn_amount, vault_amount) = pricing::split_purchase(&ctx.accounts.global_config, lxr_needed)?;
+    let vault_transfer = fee_adjusted(
+        &ctx.accounts.lxr_mint,
+        vault_amount,
+        ctx.accounts.global_config.gross_up_transfer_fees,
+    )?;
+    let lxr_paid = burn_amount.safe_add(vault_transfer.send)?;
+    require!(lxr_paid <= max_lxr_in, crate::ErrorCode::MaxLxrInExceeded);
+
+    // 1. Transfer LXR to RWA Vault
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.user_lxr_account.to_account_info(),
+            mint: ctx.accounts.lxr_mint.to_account_info(),
+            to: ctx.accounts.rwa_vault_lxr.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        }
+    );
+    token_interface::transfer_checked(transfer_ctx, vault_transfer.send, ctx.accounts.lxr_mint.decimals)?;
+
+    // 2. Burn LXR
+    let burn_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Burn {
+            mint: ctx.accounts.lxr_mint.to_account_info(),
+            from: ctx.accounts.user_lxr_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        }
+    );
+    token_interface::burn(burn_ctx, burn_amount)?;
+
+    let config = &mut ctx.accounts.global_config;
+    let record = &mut ctx.accounts.purchase_record;
+    record.owner = ctx.accounts.user.key();
+    record.bump = ctx.bumps.purchase_record;
+    pricing::record_purchase(config, record, amount_xls, now)?;
+    config.total_lxr_burned = config.total_lxr_burned.safe_add(burn_amount)?;
+    config.total_xls_sold = config.total_xls_sold.safe_add(amount_xls)?;
+    config.auction_xls_escrowed = config.auction_xls_escrowed.safe_sub(amount_xls)?;
+
+    let auction = &mut ctx.accounts.auction;
+    auction.sold_xls = auction.sold_xls.safe_add(amount_xls)?;
+    auction.lxr_raised = auction.lxr_raised.safe_add(lxr_paid)?;
+
+    // 3. Transfer XLS from Auction Vault to User
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let transfer_xls = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.auction_vault.to_account_info(),
+            mint: ctx.accounts.xls_mint.to_account_info(),
+            to: ctx.accounts.user_xls_account.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer_xls, amount_xls, ctx.accounts.xls_mint.decimals)?;
+
+    emit!(XlsPurchasedAuction {
+        buyer: ctx.accounts.user.key(),
+        auction: ctx.accounts.auction.id,
+        amount_xls,
+        price,
+        lxr_paid,
+        sold_xls: ctx.accounts.auction.sold_xls,
+    });
+
+    msg!("Auction {} Fill: {} XLS at {} LXR per XLS (Paid {} LXR)", ctx.accounts.auction.id, amount_xls, price, lxr_paid);
+    Ok(())
+}
+
+/// Returns the unsold remainder to the Supply Vault and reclaims both accounts' rent.
+/// The admin may close before `start_time + duration` to cancel a running tranche;
+/// that emits `AuctionClosedEarly` instead of `AuctionClosed` so indexers can tell them apart.
+pub fn close_auction_handler(ctx: Context<CloseAuction>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let remainder = ctx.accounts.auction_vault.amount;
+
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    if remainder > 0 {
+        let transfer_xls = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.auction_vault.to_account_info(),
+                mint: ctx.accounts.xls_mint.to_account_info(),
+                to: ctx.accounts.xls_vault_supply.to_account_info(),
+                authority: ctx.accounts.global_config.to_account_info(),
+            },
+            signer
+        );
+        token_interface::transfer_checked(transfer_xls, remainder, ctx.accounts.xls_mint.decimals)?;
+    }
+
+    let close_vault = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.auction_vault.to_account_info(),
+            destination: ctx.accounts.admin.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::close_account(close_vault)?;
+
+    // Escrow tracking follows the tranche, not the vault balance, so stray deposits cannot underflow it
+    let auction = &ctx.accounts.auction;
+    let unsold = auction.tranche_xls.safe_sub(auction.sold_xls)?;
+    let config = &mut ctx.accounts.global_config;
+    config.auction_xls_escrowed = config.auction_xls_escrowed.safe_sub(unsold)?;
+
+    let ends_at = auction.start_time.safe_add(auction.duration)?;
+    if now < ends_at {
+        emit!(AuctionClosedEarly {
+            id: auction.id,
+            sold_xls: auction.sold_xls,
+            lxr_raised: auction.lxr_raised,
+            returned_xls: remainder,
+            ends_at,
+        });
+    } else {
+        emit!(AuctionClosed {
+            id: auction.id,
+            sold_xls: auction.sold_xls,
+            lxr_raised: auction.lxr_raised,
+            returned_xls: remainder,
+        });
+    }
+
+    msg!(
+        "Auction {} Closed{}: {} XLS sold, {} XLS returned to supply",
+        auction.id, if now < ends_at { " Early" } else { "" }, auction.sold_xls, remainder
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::auction_price;
+
+    #[test]
+    fn auction_price_declines_linearly_within_window() {
+        let auction = Auction {
+            id: 0,
+            start_price: 1_000,
+            end_price: 400,
+            start_time: 100,
+            duration: 60,
+            tranche_xls: 1,
+            sold_xls: 0,
+            lxr_raised: 0,
+            bump: 0,
+        };
+        assert_eq!(auction_price(&auction, 100).unwrap(), 1_000);
+        assert_eq!(auction_price(&auction, 130).unwrap(), 700);
+        assert_eq!(auction_price(&auction, 101).unwrap(), 990);
+        assert_eq!(auction_price(&auction, 160).unwrap(), 400);
+        assert!(auction_price(&auction, 99).is_err()); // Not started
+        assert!(auction_price(&auction, 161).is_err()); // Ended
+    }
+}
@@ -0,0 +1,187 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use crate::state::*;
+use crate::pricing::{self, MintDecimals};
+use crate::events::*;
+
+#[derive(Accounts)]
+pub struct PokeRedemptionBreaker<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault)]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault)]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct SetRedemptionBreaker<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault)]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault)]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RedemptionBreakerParams {
+    pub min_coverage_bps: u16, // 0 = Disabled
+    pub override_halted: Option<bool>, // Some = Pin the halted state, None = Follow coverage
+}
+
+fn current_coverage(
+    config: &GlobalConfig,
+    xls_mint: &InterfaceAccount<Mint>,
+    lxr_mint: &InterfaceAccount<Mint>,
+    xls_vault_supply: &InterfaceAccount<TokenAccount>,
+    rwa_vault_lxr: &InterfaceAccount<TokenAccount>,
+) -> Result<u64> {
+    let circulating = pricing::circulating_xls(config, xls_mint.supply, xls_vault_supply.amount)?;
+    pricing::coverage_bps(
+        config,
+        pricing::free_vault_lxr(config, rwa_vault_lxr.amount),
+        circulating,
+        MintDecimals { xls: xls_mint.decimals, lxr: lxr_mint.decimals },
+    )
+}
+
+/// Flips `redemptions_halted` and emits Tripped/Reset only on an actual transition.
+fn apply_breaker_state(config: &mut GlobalConfig, halted: bool, coverage_bps: u64, by_admin: bool) {
+    if config.redemptions_halted == halted {
+        return;
+    }
+    config.redemptions_halted = halted;
+
+    let min_coverage_bps = config.min_coverage_bps;
+    if halted {
+        emit!(RedemptionBreakerTripped { coverage_bps, min_coverage_bps, by_admin });
+        msg!("Redemption Breaker TRIPPED: coverage {} bps < {} bps", coverage_bps, min_coverage_bps);
+    } else {
+        emit!(RedemptionBreakerReset { coverage_bps, min_coverage_bps, by_admin });
+        msg!("Redemption Breaker RESET: coverage {} bps", coverage_bps);
+    }
+}
+
+/// Permissionless crank: halts redemptions while coverage is below the threshold and resumes them once it recovers.
+pub fn poke_redemption_breaker_handler(ctx: Context<PokeRedemptionBreaker>) -> Result<()> {
+    let coverage = current_coverage(
+        &ctx.accounts.global_config,
+        &ctx.accounts.xls_mint,
+        &ctx.accounts.lxr_mint,
+        &ctx.accounts.xls_vault_supply,
+        &ctx.accounts.rwa_vault_lxr,
+    )?;
+
+    let config = &mut ctx.accounts.global_config;
+    require!(!config.breaker_overridden, crate::ErrorCode::BreakerOverridden);
+
+    let halted = config.min_coverage_bps > 0 && coverage < config.min_coverage_bps as u64;
+    apply_breaker_state(config, halted, coverage, false);
+
+    Ok(())
+}
+
+pub fn set_redemption_breaker_handler(ctx: Context<SetRedemptionBreaker>, params: RedemptionBreakerParams) -> Result<()> {
+    let coverage = current_coverage(
+        &ctx.accounts.global_config,
+        &ctx.accounts.xls_mint,
+        &ctx.accounts.lxr_mint,
+        &ctx.accounts.xls_vault_supply,
+        &ctx.accounts.rwa_vault_lxr,
+    )?;
+
+    let config = &mut ctx.accounts.global_config;
+    // Coverage is measured against the book value; without one nothing is owed and the breaker never trips
+    require!(
+        params.min_coverage_bps == 0 || config.redemption_rate > 0,
+        crate::ErrorCode::InvalidRedemptionParams
+    );
+    config.min_coverage_bps = params.min_coverage_bps;
+    config.breaker_overridden = params.override_halted.is_some();
+
+    let halted = match params.override_halted {
+        Some(halted) => halted,
+        None => config.min_coverage_bps > 0 && coverage < config.min_coverage_bps as u64,
+    };
+    apply_breaker_state(config, halted, coverage, true);
+
+    emit!(RedemptionBreakerUpdated {
+        min_coverage_bps: config.min_coverage_bps,
+        overridden: config.breaker_overridden,
+        halted: config.redemptions_halted,
+    });
+
+    msg!(
+        "Redemption Breaker: threshold {} bps, overridden {}, halted {}",
+        config.min_coverage_bps,
+        config.breaker_overridden,
+        config.redemptions_halted
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::{coverage_bps, require_coverage_after, DEFAULT_REDEMPTION_RATE};
+
+    const XLS6_LXR9: MintDecimals = MintDecimals { xls: 6, lxr: 9 };
+
+    #[test]
+    fn coverage_at_book_value_with_mismatched_decimals() {
+        let config = GlobalConfig { redemption_rate: DEFAULT_REDEMPTION_RATE, ..Default::default() };
+        // 10 XLS (6 decimals) owe 7M LXR (9 decimals); 3.5M LXR in the vault = 50%
+        let out = coverage_bps(&config, 3_500_000 * 1_000_000_000, 10_000_000, XLS6_LXR9).unwrap();
+        assert_eq!(out, 5_000);
+
+        // Nothing circulating: nothing owed
+        assert_eq!(coverage_bps(&config, 0, 0, XLS6_LXR9).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn nav_mode_breaker_halts_below_book_value_coverage() {
+        // NAV pays from the vault, but coverage is still owed at the 700k book value
+        let config = GlobalConfig {
+            redemption_mode: RedemptionMode::Nav,
+            redemption_rate: DEFAULT_REDEMPTION_RATE,
+            redemption_floor: 500_000,
+            min_coverage_bps: 5_000,
+            ..Default::default()
+        };
+        let coverage = coverage_bps(&config, 3_400_000 * 1_000_000_000, 10_000_000, XLS6_LXR9).unwrap();
+        assert!(coverage < config.min_coverage_bps as u64);
+        assert!(require_coverage_after(&config, 3_400_000 * 1_000_000_000, 10_000_000, XLS6_LXR9).is_err());
+
+        // Without a book value the same vault would read as fully covered
+        let unpriced = GlobalConfig { redemption_rate: 0, ..config };
+        assert_eq!(coverage_bps(&unpriced, 0, 10_000_000, XLS6_LXR9).unwrap(), u64::MAX);
+    }
+}
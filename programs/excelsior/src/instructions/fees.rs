@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 use crate::state::*;
+use crate::events::*;
 
 #[derive(Accounts)]
 pub struct HarvestFees<'info> {
@@ -94,6 +95,11 @@ pub fn harvest_handler(ctx: Context<HarvestFees>) -> Result<()> {
         token_interface::transfer_checked(transfer_ctx, founder_share, ctx.accounts.lxr_mint.decimals)?;
     }
     
+    emit!(FeesHarvested {
+        harvested: harvested_amount,
+        founder_share,
+    });
+    
     msg!("Harvested {} LXR. Sent {} to Founder (50%).", harvested_amount, founder_share); // Force
 
     
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
+use crate::pricing::*;
+use crate::events::*;
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
@@ -32,6 +34,9 @@ pub struct Initialize<'info> {
     pub xls_vault_supply: UncheckedAccount<'info>,
     /// CHECK: LXR Reward Vault
     pub lxr_vault_rewards: UncheckedAccount<'info>,
+    /// CHECK: XLS Staking Vault. Must not be the supply vault, or staked XLS would be sold.
+    #[account(constraint = xls_vault_staking.key() != xls_vault_supply.key() @ crate::ErrorCode::SharedStakingVault)]
+    pub xls_vault_staking: UncheckedAccount<'info>,
     
     pub system_program: Program<'info, System>,
 }
@@ -54,17 +59,68 @@ pub fn handler(ctx: Context<Initialize>, params: InitParams) -> Result<()> {
     config.rwa_vault_lxr = ctx.accounts.rwa_vault_lxr.key();
     config.xls_vault_supply = ctx.accounts.xls_vault_supply.key();
     config.lxr_vault_rewards = ctx.accounts.lxr_vault_rewards.key();
+    config.xls_vault_staking = ctx.accounts.xls_vault_staking.key();
     
     config.fee_basis_points = params.fee_basis_points;
     config.max_fee_basis_points = 300; // Hard Cap 3%
     config.total_lxr_burned = 0;
     
+    // Sale Price 1 XLS = 1,000,000 LXR (30% Burn, 70% Vault)
+    config.swap_price = DEFAULT_SWAP_PRICE;
+    config.swap_burn_bps = DEFAULT_SWAP_BURN_BPS;
+    config.swap_vault_bps = DEFAULT_SWAP_VAULT_BPS;
+    config.sale_mode = SaleMode::Fixed;
+    config.sale_tiers = [SaleTier::default(); MAX_SALE_TIERS];
+    config.total_xls_sold = 0;
+    config.gross_up_transfer_fees = false;
+    config.min_coverage_bps = 0; // Circuit Breaker disabled until the admin sets a threshold
+    config.redemptions_halted = false;
+    config.breaker_overridden = false;
+    config.redeem_epoch = 0;
+    config.redeem_epoch_duration = DEFAULT_REDEEM_EPOCH_DURATION;
+    config.redeem_lxr_reserved = 0;
+    config.lock_tiers = [LockTier::default(); MAX_LOCK_TIERS]; // Locked purchases disabled until tiers are set
+    config.wallet_cap_xls = 0; // Purchase caps disabled until the admin sets them
+    config.epoch_cap_xls = 0;
+    config.sale_epoch_duration = 0;
+    config.sale_epoch_start = 0;
+    config.sale_epoch_sold = 0;
+    config.referral_bps = 0; // Referral rewards disabled until the admin sets a share
+    config.referral_lxr_reserved = 0;
+    config.quote_signer = Pubkey::default(); // OTC disabled until the admin sets a signer
+    config.next_auction_id = 0;
+    config.auction_xls_escrowed = 0;
+    config.redemption_fee_bps = 0; // Exit fee disabled until the admin sets one
+    config.staker_redemption_fee_bps = 0;
+    config.staker_fee_threshold_xls = 0;
+    config.reward_rate_per_second = 0; // Emission disabled until the admin funds a schedule
+    config.emission_end_ts = 0;
+    config.emission_budget = 0;
+    config.undistributed_rewards = 0;
+    config.total_effective_stake = 0;
+    config.unbonding_period = 0; // Unstakes return XLS immediately until the admin sets a delay
+    config.total_unbonding_xls = 0;
+    
+    // Redemption at Book Value until Admin switches to NAV
+    config.redemption_mode = RedemptionMode::Fixed;
+    config.redemption_rate = DEFAULT_REDEMPTION_RATE;
+    config.redemption_floor = 0;
+    config.redemption_ceiling = 0;
+    
     // Set Inflation Timer
     let clock = Clock::get()?;
     config.last_inflation_timestamp = clock.unix_timestamp;
+    config.last_update_ts = clock.unix_timestamp;
     
     config.bump = ctx.bumps.global_config;
     
+    emit!(ConfigInitialized {
+        admin: config.admin,
+        xls_mint: config.xls_mint,
+        lxr_mint: config.lxr_mint,
+        fee_basis_points: config.fee_basis_points,
+    });
+    
     msg!("Excelsior Global Config Initialized (V2)");
     Ok(())
 }
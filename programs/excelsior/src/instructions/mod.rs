@@ -9,3 +9,19 @@ pub mod rewards;
 pub use rewards::*;
 pub mod admin_ops;
 pub use admin_ops::*;
+pub mod breaker;
+pub use breaker::*;
+pub mod redeem_queue;
+pub use redeem_queue::*;
+pub mod vesting;
+pub use vesting::*;
+pub mod referral;
+pub use referral::*;
+pub mod otc;
+pub use otc::*;
+pub mod auction;
+pub use auction::*;
+pub mod pool;
+pub use pool::*;
+pub mod views;
+pub use views::*;
@@ -0,0 +1,222 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use anchor_spl::token_2022;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, Burn};
+use crate::state::*;
+use crate::pricing;
+use crate::transfer_fee::fee_adjusted;
+use crate::math::SafeMath;
+use crate::events::*;
+use crate::ed25519::verify_ed25519_ix;
+
+#[derive(Accounts)]
+#[instruction(amount_xls: u64, price: u64, nonce: u64)]
+pub struct BuyXlsOtc<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"purchase_record", user.key().as_ref()],
+        bump,
+        space = PurchaseRecord::LEN
+    )]
+    pub purchase_record: Box<Account<'info, PurchaseRecord>>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"otc_nonce", user.key().as_ref(), nonce.to_le_bytes().as_ref()],
+        bump,
+        space = OtcNonce::LEN
+    )] // Fails if the quote was already filled
+    pub otc_nonce: Box<Account<'info, OtcNonce>>,
+
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault,
+        constraint = xls_vault_supply.mint == xls_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = xls_vault_supply.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault,
+        constraint = rwa_vault_lxr.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = rwa_vault_lxr.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Instructions sysvar, read to find the preceding Ed25519 verification
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Domain tag binding a quote signature to this message format.
+pub const OTC_QUOTE_DOMAIN: &[u8] = b"excelsior-otc-v1";
+
+/// Bytes the quote signer signs:
+/// program_id || global_config || OTC_QUOTE_DOMAIN || buyer || amount_xls || price || nonce || expires_at (integers little-endian).
+/// The prefix keeps a signature from being replayed on another deployment or reused for another message.
+pub fn otc_quote_message(
+    global_config: &Pubkey,
+    buyer: &Pubkey,
+    amount_xls: u64,
+    price: u64,
+    nonce: u64,
+    expires_at: i64,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 * 3 + OTC_QUOTE_DOMAIN.len() + 8 * 4);
+    message.extend_from_slice(crate::ID.as_ref());
+    message.extend_from_slice(global_config.as_ref());
+    message.extend_from_slice(OTC_QUOTE_DOMAIN);
+    message.extend_from_slice(buyer.as_ref());
+    message.extend_from_slice(&amount_xls.to_le_bytes());
+    message.extend_from_slice(&price.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message.extend_from_slice(&expires_at.to_le_bytes());
+    message
+}
+
+pub fn buy_otc_handler(
+    ctx: Context<BuyXlsOtc>,
+    amount_xls: u64,
+    price: u64,
+    nonce: u64,
+    expires_at: i64,
+) -> Result<()> {
+    require!(amount_xls > 0, crate::ErrorCode::ZeroAmount);
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= expires_at, crate::ErrorCode::QuoteExpired);
+    require!(ctx.accounts.xls_vault_supply.amount >= amount_xls, crate::ErrorCode::VaultInsufficient);
+
+    let quote_signer = ctx.accounts.global_config.quote_signer;
+    require!(quote_signer != Pubkey::default(), crate::ErrorCode::OtcDisabled);
+
+    // The Ed25519 verification must immediately precede this instruction
+    let instructions = ctx.accounts.instructions.to_account_info();
+    let current = load_current_index_checked(&instructions)?;
+    require!(current > 0, crate::ErrorCode::MissingEd25519Instruction);
+    let ed25519_ix = load_instruction_at_checked((current - 1) as usize, &instructions)?;
+    verify_ed25519_ix(
+        &ed25519_ix,
+        &quote_signer,
+        &otc_quote_message(
+            &ctx.accounts.global_config.key(),
+            &ctx.accounts.user.key(),
+            amount_xls,
+            price,
+            nonce,
+            expires_at,
+        ),
+    )?;
+
+    // The negotiated price replaces the configured sale price for this fill; the split is unchanged
+    let lxr_needed = pricing::quote_buy_with(
+        price,
+        amount_xls,
+        ctx.accounts.xls_mint.decimals,
+        ctx.accounts.lxr_mint.decimals,
+    )?;
+    let (burn_amount, vault_amount) = pricing::split_purchase(&ctx.accounts.global_config, lxr_needed)?;
+    let vault_transfer = fee_adjusted(
+        &ctx.accounts.lxr_mint,
+        vault_amount,
+        ctx.accounts.global_config.gross_up_transfer_fees,
+    )?;
+    let lxr_paid = burn_amount.safe_add(vault_transfer.send)?;
+
+    // 1. Transfer LXR to RWA Vault
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.user_lxr_account.to_account_info(),
+            mint: ctx.accounts.lxr_mint.to_account_info(),
+            to: ctx.accounts.rwa_vault_lxr.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        }
+    );
+    token_interface::transfer_checked(transfer_ctx, vault_transfer.send, ctx.accounts.lxr_mint.decimals)?;
+
+    // 2. Burn LXR
+    let burn_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Burn {
+            mint: ctx.accounts.lxr_mint.to_account_info(),
+            from: ctx.accounts.user_lxr_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        }
+    );
+    token_interface::burn(burn_ctx, burn_amount)?;
+
+    let config = &mut ctx.accounts.global_config;
+    let record = &mut ctx.accounts.purchase_record;
+    record.owner = ctx.accounts.user.key();
+    record.bump = ctx.bumps.purchase_record;
+    pricing::record_purchase(config, record, amount_xls, now)?;
+    config.total_lxr_burned = config.total_lxr_burned.safe_add(burn_amount)?;
+    config.total_xls_sold = config.total_xls_sold.safe_add(amount_xls)?;
+
+    let otc_nonce = &mut ctx.accounts.otc_nonce;
+    otc_nonce.buyer = ctx.accounts.user.key();
+    otc_nonce.nonce = nonce;
+    otc_nonce.bump = ctx.bumps.otc_nonce;
+
+    // 3. Transfer XLS from Supply Vault to User
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let transfer_xls = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.xls_vault_supply.to_account_info(),
+            mint: ctx.accounts.xls_mint.to_account_info(),
+            to: ctx.accounts.user_xls_account.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer_xls, amount_xls, ctx.accounts.xls_mint.decimals)?;
+
+    emit!(XlsPurchasedOtc {
+        buyer: ctx.accounts.user.key(),
+        amount_xls,
+        price,
+        nonce,
+        lxr_paid,
+        total_xls_sold: ctx.accounts.global_config.total_xls_sold,
+    });
+
+    msg!("OTC Fill: {} XLS at {} LXR per XLS (Paid {} LXR, nonce {})", amount_xls, price, lxr_paid, nonce);
+    Ok(())
+}
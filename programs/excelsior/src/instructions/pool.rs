@@ -0,0 +1,600 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, MintTo, Burn};
+use crate::state::*;
+use crate::pricing::{self, MAX_POOL_FEE_BPS};
+use crate::transfer_fee::fee_adjusted;
+use crate::math::*;
+use crate::events::*;
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pool"],
+        bump,
+        space = Pool::LEN
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pool_lxr_vault"],
+        bump,
+        token::mint = lxr_mint,
+        token::authority = global_config,
+        token::token_program = token_program,
+    )]
+    pub pool_lxr_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pool_xls_vault"],
+        bump,
+        token::mint = xls_mint,
+        token::authority = global_config,
+        token::token_program = token_program,
+    )]
+    pub pool_xls_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"lp_mint"],
+        bump,
+        mint::decimals = xls_mint.decimals,
+        mint::authority = global_config,
+        mint::token_program = token_program,
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPoolFees<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool"],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+}
+
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool"],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_lxr_vault"],
+        bump,
+    )]
+    pub pool_lxr_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"pool_xls_vault"],
+        bump,
+    )]
+    pub pool_xls_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"lp_mint"],
+        bump,
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_lp_account.mint == lp_mint.key() @ crate::ErrorCode::UserLpMintMismatch,
+        constraint = user_lp_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lp_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SwapPool<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool"],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_lxr_vault"],
+        bump,
+    )]
+    pub pool_lxr_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"pool_xls_vault"],
+        bump,
+    )]
+    pub pool_xls_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault,
+        constraint = rwa_vault_lxr.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = rwa_vault_lxr.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PoolFeeParams {
+    pub fee_bps: u16,
+    pub protocol_fee_bps: u16, // Share of fee_bps, not of the trade
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SwapDirection {
+    LxrToXls,
+    XlsToLxr,
+}
+
+fn validate_pool_fees(params: &PoolFeeParams) -> Result<()> {
+    require!(
+        params.fee_bps <= MAX_POOL_FEE_BPS && params.protocol_fee_bps as u64 <= BPS_DENOMINATOR,
+        crate::ErrorCode::InvalidPoolParams
+    );
+    Ok(())
+}
+
+/// Pool-signed LXR/XLS transfer out of a pool vault.
+fn pool_transfer<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    global_config: &Account<'info, GlobalConfig>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[b"global_config".as_ref(), &[global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let transfer = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TransferChecked {
+            from: from.to_account_info(),
+            mint: mint.to_account_info(),
+            to,
+            authority: global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer, amount, mint.decimals)
+}
+
+/// User-signed transfer into the pool or the RWA Vault.
+fn user_transfer<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    user: &Signer<'info>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let transfer = CpiContext::new(
+        token_program.to_account_info(),
+        TransferChecked {
+            from: from.to_account_info(),
+            mint: mint.to_account_info(),
+            to,
+            authority: user.to_account_info(),
+        }
+    );
+    token_interface::transfer_checked(transfer, amount, mint.decimals)
+}
+
+pub fn init_pool_handler(ctx: Context<InitPool>, params: PoolFeeParams) -> Result<()> {
+    validate_pool_fees(&params)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.lxr_reserve = 0;
+    pool.xls_reserve = 0;
+    pool.fee_bps = params.fee_bps;
+    pool.protocol_fee_bps = params.protocol_fee_bps;
+    pool.total_protocol_lxr = 0;
+    pool.bump = ctx.bumps.pool;
+
+    emit!(PoolFeesUpdated {
+        fee_bps: params.fee_bps,
+        protocol_fee_bps: params.protocol_fee_bps,
+    });
+
+    msg!("LXR/XLS Pool Initialized: Fee {} bps ({} bps of it to the RWA Vault)", params.fee_bps, params.protocol_fee_bps);
+    Ok(())
+}
+
+pub fn set_pool_fees_handler(ctx: Context<SetPoolFees>, params: PoolFeeParams) -> Result<()> {
+    validate_pool_fees(&params)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.fee_bps = params.fee_bps;
+    pool.protocol_fee_bps = params.protocol_fee_bps;
+
+    emit!(PoolFeesUpdated {
+        fee_bps: params.fee_bps,
+        protocol_fee_bps: params.protocol_fee_bps,
+    });
+
+    msg!("Pool Fees: {} bps ({} bps of it to the RWA Vault)", params.fee_bps, params.protocol_fee_bps);
+    Ok(())
+}
+
+/// `max_lxr_in` / `max_xls_in` bound what leaves the wallet; only the amounts matching the pool ratio are taken.
+pub fn add_liquidity_handler(
+    ctx: Context<ModifyLiquidity>,
+    max_lxr_in: u64,
+    max_xls_in: u64,
+    min_lp_out: u64,
+) -> Result<()> {
+    require!(max_lxr_in > 0 && max_xls_in > 0, crate::ErrorCode::ZeroAmount);
+
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    // The pool is credited with what arrives after the LXR transfer fee
+    let lxr_available = fee_adjusted(&ctx.accounts.lxr_mint, max_lxr_in, false)?.received;
+    let lp_out = pricing::lp_for_deposit(&ctx.accounts.pool, lxr_available, max_xls_in, lp_supply)?;
+    require!(lp_out > 0, crate::ErrorCode::ZeroAmount);
+    require!(lp_out >= min_lp_out, crate::ErrorCode::MinLpOutNotMet);
+
+    let (lxr_credit, xls_in) = if lp_supply == 0 {
+        (lxr_available, max_xls_in)
+    } else {
+        let pool = &ctx.accounts.pool;
+        (
+            pricing::pool_share(pool.lxr_reserve, lp_out, lp_supply, true)?,
+            pricing::pool_share(pool.xls_reserve, lp_out, lp_supply, true)?,
+        )
+    };
+    let lxr_send = if lp_supply == 0 {
+        max_lxr_in
+    } else {
+        fee_adjusted(&ctx.accounts.lxr_mint, lxr_credit, true)?.send
+    };
+    require!(lxr_send <= max_lxr_in, crate::ErrorCode::MaxLxrInExceeded);
+
+    user_transfer(
+        &ctx.accounts.token_program,
+        &ctx.accounts.user,
+        &ctx.accounts.user_lxr_account,
+        &ctx.accounts.lxr_mint,
+        ctx.accounts.pool_lxr_vault.to_account_info(),
+        lxr_send,
+    )?;
+    user_transfer(
+        &ctx.accounts.token_program,
+        &ctx.accounts.user,
+        &ctx.accounts.user_xls_account,
+        &ctx.accounts.xls_mint,
+        ctx.accounts.pool_xls_vault.to_account_info(),
+        xls_in,
+    )?;
+
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let mint_lp = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        MintTo {
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            to: ctx.accounts.user_lp_account.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::mint_to(mint_lp, lp_out)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.lxr_reserve = pool.lxr_reserve.safe_add(lxr_credit)?;
+    pool.xls_reserve = pool.xls_reserve.safe_add(xls_in)?;
+
+    emit!(LiquidityAdded {
+        provider: ctx.accounts.user.key(),
+        lxr_in: lxr_credit,
+        xls_in,
+        lp_minted: lp_out,
+        lxr_reserve: pool.lxr_reserve,
+        xls_reserve: pool.xls_reserve,
+    });
+
+    msg!("Liquidity Added: {} LXR + {} XLS for {} LP", lxr_credit, xls_in, lp_out);
+    Ok(())
+}
+
+/// `min_lxr_out` is checked against what reaches the wallet after the LXR transfer fee.
+pub fn remove_liquidity_handler(
+    ctx: Context<ModifyLiquidity>,
+    lp_amount: u64,
+    min_lxr_out: u64,
+    min_xls_out: u64,
+) -> Result<()> {
+    require!(lp_amount > 0, crate::ErrorCode::ZeroAmount);
+
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    let pool = &ctx.accounts.pool;
+    let lxr_out = pricing::pool_share(pool.lxr_reserve, lp_amount, lp_supply, false)?;
+    let xls_out = pricing::pool_share(pool.xls_reserve, lp_amount, lp_supply, false)?;
+
+    let lxr_received = fee_adjusted(&ctx.accounts.lxr_mint, lxr_out, false)?.received;
+    require!(lxr_received >= min_lxr_out, crate::ErrorCode::MinLxrOutNotMet);
+    require!(xls_out >= min_xls_out, crate::ErrorCode::MinAmountOutNotMet);
+
+    let burn_lp = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Burn {
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            from: ctx.accounts.user_lp_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        }
+    );
+    token_interface::burn(burn_lp, lp_amount)?;
+
+    pool_transfer(
+        &ctx.accounts.token_program,
+        &ctx.accounts.global_config,
+        &ctx.accounts.pool_lxr_vault,
+        &ctx.accounts.lxr_mint,
+        ctx.accounts.user_lxr_account.to_account_info(),
+        lxr_out,
+    )?;
+    pool_transfer(
+        &ctx.accounts.token_program,
+        &ctx.accounts.global_config,
+        &ctx.accounts.pool_xls_vault,
+        &ctx.accounts.xls_mint,
+        ctx.accounts.user_xls_account.to_account_info(),
+        xls_out,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.lxr_reserve = pool.lxr_reserve.safe_sub(lxr_out)?;
+    pool.xls_reserve = pool.xls_reserve.safe_sub(xls_out)?;
+
+    emit!(LiquidityRemoved {
+        provider: ctx.accounts.user.key(),
+        lxr_out,
+        xls_out,
+        lp_burned: lp_amount,
+        lxr_reserve: pool.lxr_reserve,
+        xls_reserve: pool.xls_reserve,
+    });
+
+    msg!("Liquidity Removed: {} LP for {} LXR + {} XLS", lp_amount, lxr_out, xls_out);
+    Ok(())
+}
+
+/// The swap fee is always taken on the LXR leg: from the input when selling LXR, from the output when selling XLS.
+/// The protocol share goes to the RWA Vault and the rest stays in the pool for LPs.
+/// For LXR output, `min_amount_out` is checked against what reaches the wallet after the transfer fee.
+pub fn swap_pool_handler(
+    ctx: Context<SwapPool>,
+    amount_in: u64,
+    min_amount_out: u64,
+    direction: SwapDirection,
+) -> Result<()> {
+    require!(amount_in > 0, crate::ErrorCode::ZeroAmount);
+
+    let pool = &ctx.accounts.pool;
+    let (amount_out, protocol_lxr) = match direction {
+        SwapDirection::LxrToXls => {
+            let fee = bps_of(amount_in, pool.fee_bps as u64)?;
+            let protocol_lxr = bps_of(fee, pool.protocol_fee_bps as u64)?;
+            let to_pool = amount_in.safe_sub(protocol_lxr)?;
+
+            // Only LXR that actually lands in the vault moves the curve
+            let pool_received = fee_adjusted(&ctx.accounts.lxr_mint, to_pool, false)?.received;
+            let net_in = pool_received.safe_sub(fee.safe_sub(protocol_lxr)?)?;
+            let xls_out = pricing::pool_swap_out(pool.lxr_reserve, pool.xls_reserve, net_in)?;
+            require!(xls_out > 0, crate::ErrorCode::ZeroAmount);
+            require!(xls_out >= min_amount_out, crate::ErrorCode::MinAmountOutNotMet);
+
+            user_transfer(
+                &ctx.accounts.token_program,
+                &ctx.accounts.user,
+                &ctx.accounts.user_lxr_account,
+                &ctx.accounts.lxr_mint,
+                ctx.accounts.pool_lxr_vault.to_account_info(),
+                to_pool,
+            )?;
+            if protocol_lxr > 0 {
+                user_transfer(
+                    &ctx.accounts.token_program,
+                    &ctx.accounts.user,
+                    &ctx.accounts.user_lxr_account,
+                    &ctx.accounts.lxr_mint,
+                    ctx.accounts.rwa_vault_lxr.to_account_info(),
+                    protocol_lxr,
+                )?;
+            }
+            pool_transfer(
+                &ctx.accounts.token_program,
+                &ctx.accounts.global_config,
+                &ctx.accounts.pool_xls_vault,
+                &ctx.accounts.xls_mint,
+                ctx.accounts.user_xls_account.to_account_info(),
+                xls_out,
+            )?;
+
+            let pool = &mut ctx.accounts.pool;
+            pool.lxr_reserve = pool.lxr_reserve.safe_add(pool_received)?; // LP fee stays in the reserve
+            pool.xls_reserve = pool.xls_reserve.safe_sub(xls_out)?;
+            (xls_out, protocol_lxr)
+        }
+        SwapDirection::XlsToLxr => {
+            let gross_out = pricing::pool_swap_out(pool.xls_reserve, pool.lxr_reserve, amount_in)?;
+            let fee = bps_of(gross_out, pool.fee_bps as u64)?;
+            let protocol_lxr = bps_of(fee, pool.protocol_fee_bps as u64)?;
+            let lxr_out = gross_out.safe_sub(fee)?;
+            require!(lxr_out > 0, crate::ErrorCode::ZeroAmount);
+
+            let lxr_received = fee_adjusted(&ctx.accounts.lxr_mint, lxr_out, false)?.received;
+            require!(lxr_received >= min_amount_out, crate::ErrorCode::MinAmountOutNotMet);
+
+            user_transfer(
+                &ctx.accounts.token_program,
+                &ctx.accounts.user,
+                &ctx.accounts.user_xls_account,
+                &ctx.accounts.xls_mint,
+                ctx.accounts.pool_xls_vault.to_account_info(),
+                amount_in,
+            )?;
+            pool_transfer(
+                &ctx.accounts.token_program,
+                &ctx.accounts.global_config,
+                &ctx.accounts.pool_lxr_vault,
+                &ctx.accounts.lxr_mint,
+                ctx.accounts.user_lxr_account.to_account_info(),
+                lxr_out,
+            )?;
+            if protocol_lxr > 0 {
+                pool_transfer(
+                    &ctx.accounts.token_program,
+                    &ctx.accounts.global_config,
+                    &ctx.accounts.pool_lxr_vault,
+                    &ctx.accounts.lxr_mint,
+                    ctx.accounts.rwa_vault_lxr.to_account_info(),
+                    protocol_lxr,
+                )?;
+            }
+
+            let pool = &mut ctx.accounts.pool;
+            pool.xls_reserve = pool.xls_reserve.safe_add(amount_in)?;
+            pool.lxr_reserve = pool.lxr_reserve.safe_sub(lxr_out.safe_add(protocol_lxr)?)?;
+            (lxr_received, protocol_lxr)
+        }
+    };
+
+    let pool = &mut ctx.accounts.pool;
+    pool.total_protocol_lxr = pool.total_protocol_lxr.safe_add(protocol_lxr)?;
+
+    emit!(PoolSwapped {
+        user: ctx.accounts.user.key(),
+        lxr_to_xls: direction == SwapDirection::LxrToXls,
+        amount_in,
+        amount_out,
+        protocol_lxr,
+        lxr_reserve: pool.lxr_reserve,
+        xls_reserve: pool.xls_reserve,
+    });
+
+    msg!("Pool Swap: {} in, {} out ({} LXR to RWA Vault)", amount_in, amount_out, protocol_lxr);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::{pool_swap_out, lp_for_deposit, pool_share};
+
+    #[test]
+    fn pool_math_keeps_k_and_mints_pro_rata() {
+        // 1,000 LXR / 100 XLS: 100 LXR in buys 100 * 100 / 1,100 = 9 XLS (rounded down)
+        assert_eq!(pool_swap_out(1_000, 100, 100).unwrap(), 9);
+        assert!(pool_swap_out(0, 100, 1).is_err());
+
+        let mut pool = Pool { lxr_reserve: 0, xls_reserve: 0, fee_bps: 30, protocol_fee_bps: 0, total_protocol_lxr: 0, bump: 0 };
+        assert_eq!(lp_for_deposit(&pool, 1_000, 10, 0).unwrap(), 100); // sqrt(10,000)
+
+        pool.lxr_reserve = 1_000;
+        pool.xls_reserve = 10;
+        // Off-ratio deposit earns for the scarcer side only
+        assert_eq!(lp_for_deposit(&pool, 500, 10, 100).unwrap(), 50);
+        assert_eq!(pool_share(pool.xls_reserve, 50, 100, true).unwrap(), 5);
+        assert_eq!(pool_share(7, 50, 100, false).unwrap(), 3);
+        assert_eq!(pool_share(7, 50, 100, true).unwrap(), 4);
+    }
+}
@@ -0,0 +1,498 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, Burn};
+use crate::state::*;
+use crate::pricing::{self, MintDecimals};
+use crate::transfer_fee::fee_adjusted;
+use crate::math::*;
+use crate::events::*;
+
+#[derive(Accounts)]
+pub struct RequestRedeem<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"redeem_epoch", global_config.redeem_epoch.to_le_bytes().as_ref()],
+        bump,
+        space = RedeemEpoch::LEN
+    )]
+    pub redeem_epoch: Box<Account<'info, RedeemEpoch>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"redeem_ticket", redeem_epoch.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = RedeemTicket::LEN
+    )]
+    pub redeem_ticket: Box<Account<'info, RedeemTicket>>,
+
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"redeem_escrow"],
+        bump,
+        token::mint = xls_mint,
+        token::authority = global_config,
+        token::token_program = token_program,
+    )] // XLS waiting in the Redemption Queue
+    pub xls_redeem_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRedeem<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"redeem_epoch", redeem_ticket.epoch.to_le_bytes().as_ref()],
+        bump = redeem_epoch.bump,
+    )]
+    pub redeem_epoch: Box<Account<'info, RedeemEpoch>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"redeem_ticket", redeem_epoch.key().as_ref(), user.key().as_ref()],
+        bump = redeem_ticket.bump,
+    )]
+    pub redeem_ticket: Box<Account<'info, RedeemTicket>>,
+
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"redeem_escrow"],
+        bump,
+    )]
+    pub xls_redeem_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SettleRedeemEpoch<'info> {
+    pub cranker: Signer<'info>, // Anyone once the epoch has run its course; the admin at any time
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"redeem_epoch", global_config.redeem_epoch.to_le_bytes().as_ref()],
+        bump = redeem_epoch.bump,
+    )]
+    pub redeem_epoch: Box<Account<'info, RedeemEpoch>>,
+
+    #[account(
+        mut,
+        seeds = [b"redeem_escrow"],
+        bump,
+    )]
+    pub xls_redeem_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault)]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault)]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRedeem<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        seeds = [b"redeem_epoch", redeem_ticket.epoch.to_le_bytes().as_ref()],
+        bump = redeem_epoch.bump,
+    )]
+    pub redeem_epoch: Box<Account<'info, RedeemEpoch>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"redeem_ticket", redeem_epoch.key().as_ref(), user.key().as_ref()],
+        bump = redeem_ticket.bump,
+    )]
+    pub redeem_ticket: Box<Account<'info, RedeemTicket>>,
+
+    #[account(
+        seeds = [b"user_account", user.key().as_ref()],
+        bump = user_account.bump,
+    )] // Optional: stakers above the threshold pay the reduced exit fee
+    pub user_account: Option<Box<Account<'info, UserAccount>>>,
+
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"redeem_escrow"],
+        bump,
+    )]
+    pub xls_redeem_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault,
+        constraint = rwa_vault_lxr.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = rwa_vault_lxr.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+    )] // Receives the exit fee
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn request_redeem_handler(ctx: Context<RequestRedeem>, amount_xls: u64) -> Result<()> {
+    require!(amount_xls > 0, crate::ErrorCode::ZeroAmount);
+
+    // Escrow the XLS; it stays circulating until settlement burns the filled share
+    let transfer_xls = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.user_xls_account.to_account_info(),
+            mint: ctx.accounts.xls_mint.to_account_info(),
+            to: ctx.accounts.xls_redeem_escrow.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        }
+    );
+    token_interface::transfer_checked(transfer_xls, amount_xls, ctx.accounts.xls_mint.decimals)?;
+
+    let epoch_id = ctx.accounts.global_config.redeem_epoch;
+    let epoch = &mut ctx.accounts.redeem_epoch;
+    if epoch.opened_at == 0 {
+        epoch.id = epoch_id;
+        epoch.opened_at = Clock::get()?.unix_timestamp;
+        epoch.bump = ctx.bumps.redeem_epoch;
+    }
+    epoch.xls_requested = epoch.xls_requested.safe_add(amount_xls)?;
+
+    let ticket = &mut ctx.accounts.redeem_ticket;
+    ticket.owner = ctx.accounts.user.key();
+    ticket.epoch = epoch_id;
+    ticket.xls_amount = ticket.xls_amount.safe_add(amount_xls)?;
+    ticket.bump = ctx.bumps.redeem_ticket;
+
+    emit!(RedeemRequested {
+        user: ticket.owner,
+        epoch: epoch_id,
+        amount_xls,
+        ticket_xls: ticket.xls_amount,
+    });
+
+    msg!("Queued {} XLS for redemption in epoch {}", amount_xls, epoch_id);
+    Ok(())
+}
+
+pub fn cancel_redeem_handler(ctx: Context<CancelRedeem>) -> Result<()> {
+    require!(!ctx.accounts.redeem_epoch.settled, crate::ErrorCode::RedeemEpochSettled);
+
+    let amount_xls = ctx.accounts.redeem_ticket.xls_amount;
+    let epoch = &mut ctx.accounts.redeem_epoch;
+    epoch.xls_requested = epoch.xls_requested.safe_sub(amount_xls)?;
+
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let return_xls = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.xls_redeem_escrow.to_account_info(),
+            mint: ctx.accounts.xls_mint.to_account_info(),
+            to: ctx.accounts.user_xls_account.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(return_xls, amount_xls, ctx.accounts.xls_mint.decimals)?;
+
+    emit!(RedeemCancelled {
+        user: ctx.accounts.user.key(),
+        epoch: epoch.id,
+        amount_xls,
+    });
+
+    msg!("Cancelled redemption of {} XLS in epoch {}", amount_xls, epoch.id);
+    Ok(())
+}
+
+/// Fills the epoch pro-rata from the free RWA Vault liquidity, burns the filled XLS and reserves the LXR for claims.
+pub fn settle_redeem_epoch_handler(ctx: Context<SettleRedeemEpoch>) -> Result<()> {
+    let config = &ctx.accounts.global_config;
+    require!(!config.redemptions_halted, crate::ErrorCode::RedemptionsHalted);
+
+    let epoch = &ctx.accounts.redeem_epoch;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        ctx.accounts.cranker.key() == config.admin
+            || now >= epoch.opened_at.safe_add(config.redeem_epoch_duration)?,
+        crate::ErrorCode::RedeemEpochNotReady
+    );
+
+    let decimals = MintDecimals {
+        xls: ctx.accounts.xls_mint.decimals,
+        lxr: ctx.accounts.lxr_mint.decimals,
+    };
+    let vault_lxr = pricing::free_vault_lxr(config, ctx.accounts.rwa_vault_lxr.amount);
+    let circulating = pricing::circulating_xls(
+        config,
+        ctx.accounts.xls_mint.supply,
+        ctx.accounts.xls_vault_supply.amount,
+    )?;
+
+    // Owed at the epoch's price; if the vault is short, every ticket is filled by the same fraction
+    let xls_requested = epoch.xls_requested;
+    let lxr_owed = pricing::redemption_owed(config, xls_requested, vault_lxr, circulating, decimals)?;
+    let (xls_filled, lxr_settled) = if lxr_owed <= vault_lxr {
+        (xls_requested, lxr_owed)
+    } else {
+        let xls_filled = mul_div(xls_requested, vault_lxr, lxr_owed)?;
+        (xls_filled, mul_div(lxr_owed, xls_filled, xls_requested)?)
+    };
+
+    // Same Circuit Breaker as an instant redemption, measured on the whole epoch
+    pricing::require_coverage_after(
+        config,
+        vault_lxr.safe_sub(lxr_settled)?,
+        circulating.saturating_sub(xls_filled),
+        decimals,
+    )?;
+
+    if xls_filled > 0 {
+        let seeds = &[b"global_config".as_ref(), &[config.bump]];
+        let signer = &[&seeds[..]];
+
+        let burn_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.xls_mint.to_account_info(),
+                from: ctx.accounts.xls_redeem_escrow.to_account_info(),
+                authority: ctx.accounts.global_config.to_account_info(),
+            },
+            signer
+        );
+        token_interface::burn(burn_ctx, xls_filled)?;
+    }
+
+    let epoch = &mut ctx.accounts.redeem_epoch;
+    epoch.settled = true;
+    epoch.xls_filled = xls_filled;
+    epoch.lxr_settled = lxr_settled;
+
+    let config = &mut ctx.accounts.global_config;
+    config.redeem_lxr_reserved = config.redeem_lxr_reserved.safe_add(lxr_settled)?;
+    config.redeem_epoch = config.redeem_epoch.safe_add(1)?;
+
+    emit!(RedeemEpochSettled {
+        epoch: epoch.id,
+        xls_requested,
+        xls_filled,
+        lxr_settled,
+    });
+
+    msg!("Settled epoch {}: {}/{} XLS filled for {} LXR", epoch.id, xls_filled, xls_requested, lxr_settled);
+    Ok(())
+}
+
+/// Pays the ticket's share of the settled LXR, less the exit fee, and returns its unfilled XLS.
+pub fn claim_redeem_handler(ctx: Context<ClaimRedeem>) -> Result<()> {
+    let epoch = &ctx.accounts.redeem_epoch;
+    require!(epoch.settled, crate::ErrorCode::RedeemEpochNotSettled);
+
+    // Burned share rounds up and LXR rounds down, so the escrow and reserve never come up short
+    let ticket_xls = ctx.accounts.redeem_ticket.xls_amount;
+    let lxr_out = mul_div(ticket_xls, epoch.lxr_settled, epoch.xls_requested)?;
+    let xls_burned = to_u64(
+        (ticket_xls as u128)
+            .safe_mul(epoch.xls_filled as u128)?
+            .div_ceil(epoch.xls_requested as u128),
+    )?;
+    let xls_returned = ticket_xls.safe_sub(xls_burned)?;
+
+    // Same exit fee and staker tier as an instant redemption; with nobody staked it stays in the RWA Vault
+    let config = &ctx.accounts.global_config;
+    let staked_xls = ctx.accounts.user_account.as_ref().map_or(0, |account| account.staked_xls);
+    let exit_fee = bps_of(lxr_out, pricing::redemption_fee_bps(config, staked_xls) as u64)?;
+    let fee_to_stakers = if config.total_effective_stake > 0 { exit_fee } else { 0 };
+    let lxr_payout = lxr_out.safe_sub(exit_fee)?;
+
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let mut lxr_received = 0;
+    if lxr_payout > 0 {
+        // Never grossed up: only `lxr_out` is reserved in the RWA Vault
+        let lxr_transfer = fee_adjusted(&ctx.accounts.lxr_mint, lxr_payout, false)?;
+        require!(
+            ctx.accounts.rwa_vault_lxr.amount >= lxr_transfer.send.safe_add(fee_to_stakers)?,
+            crate::ErrorCode::VaultInsufficient
+        );
+
+        let transfer_lxr = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.rwa_vault_lxr.to_account_info(),
+                mint: ctx.accounts.lxr_mint.to_account_info(),
+                to: ctx.accounts.user_lxr_account.to_account_info(),
+                authority: ctx.accounts.global_config.to_account_info(),
+            },
+            signer
+        );
+        token_interface::transfer_checked(transfer_lxr, lxr_transfer.send, ctx.accounts.lxr_mint.decimals)?;
+        lxr_received = lxr_transfer.received;
+    }
+
+    if fee_to_stakers > 0 {
+        let transfer_fee = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.rwa_vault_lxr.to_account_info(),
+                mint: ctx.accounts.lxr_mint.to_account_info(),
+                to: ctx.accounts.lxr_vault_rewards.to_account_info(),
+                authority: ctx.accounts.global_config.to_account_info(),
+            },
+            signer
+        );
+        token_interface::transfer_checked(transfer_fee, fee_to_stakers, ctx.accounts.lxr_mint.decimals)?;
+
+        // Only what the Reward Vault actually received is owed to stakers
+        let fee_received = fee_adjusted(&ctx.accounts.lxr_mint, fee_to_stakers, false)?.received;
+        pricing::credit_stakers(&mut ctx.accounts.global_config, fee_received)?;
+    }
+
+    if xls_returned > 0 {
+        let return_xls = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.xls_redeem_escrow.to_account_info(),
+                mint: ctx.accounts.xls_mint.to_account_info(),
+                to: ctx.accounts.user_xls_account.to_account_info(),
+                authority: ctx.accounts.global_config.to_account_info(),
+            },
+            signer
+        );
+        token_interface::transfer_checked(return_xls, xls_returned, ctx.accounts.xls_mint.decimals)?;
+    }
+
+    let config = &mut ctx.accounts.global_config;
+    config.redeem_lxr_reserved = config.redeem_lxr_reserved.safe_sub(lxr_out)?;
+
+    emit!(RedeemClaimed {
+        user: ctx.accounts.user.key(),
+        epoch: ctx.accounts.redeem_ticket.epoch,
+        xls_burned,
+        xls_returned,
+        lxr_received,
+        exit_fee,
+    });
+
+    msg!(
+        "Claimed {} LXR for {} XLS (Exit Fee {} LXR); {} XLS returned",
+        lxr_received, xls_burned, exit_fee, xls_returned
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::{require_coverage_after, DEFAULT_REDEMPTION_RATE};
+
+    const XLS6_LXR9: MintDecimals = MintDecimals { xls: 6, lxr: 9 };
+
+    #[test]
+    fn coverage_gate_rejects_redemptions_that_drain_the_vault() {
+        // 10 XLS owed 7M LXR at Book Value; the breaker requires 50% coverage
+        let mut config = GlobalConfig { redemption_rate: DEFAULT_REDEMPTION_RATE, min_coverage_bps: 5_000, ..Default::default() };
+        assert!(require_coverage_after(&config, 3_500_000 * 1_000_000_000, 10_000_000, XLS6_LXR9).is_ok());
+        assert!(require_coverage_after(&config, 3_400_000 * 1_000_000_000, 10_000_000, XLS6_LXR9).is_err());
+
+        // Redeeming the last XLS leaves nothing owed
+        assert!(require_coverage_after(&config, 0, 0, XLS6_LXR9).is_ok());
+
+        config.breaker_overridden = true;
+        assert!(require_coverage_after(&config, 3_400_000 * 1_000_000_000, 10_000_000, XLS6_LXR9).is_ok());
+        config.breaker_overridden = false;
+        config.min_coverage_bps = 0;
+        assert!(require_coverage_after(&config, 0, 10_000_000, XLS6_LXR9).is_ok());
+    }
+}
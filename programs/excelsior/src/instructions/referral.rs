@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use crate::state::*;
+use crate::transfer_fee::fee_adjusted;
+use crate::math::SafeMath;
+use crate::events::*;
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"referrer", user.key().as_ref()],
+        bump,
+        space = ReferrerAccount::LEN
+    )]
+    pub referrer_account: Account<'info, ReferrerAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferral<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"referrer", user.key().as_ref()],
+        bump = referrer_account.bump,
+    )]
+    pub referrer_account: Box<Account<'info, ReferrerAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault,
+        constraint = rwa_vault_lxr.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = rwa_vault_lxr.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Validates the buyer/referrer pair and credits the referrer's share.
+/// `referrer_record` is the referrer's own PurchaseRecord address; if it exists, its bound referrer
+/// must not be the buyer, which rules out A -> B -> A loops.
+/// `buyer_referrer` is the buyer's own ReferrerAccount address. A buyer can only be bound while nobody
+/// has been referred by them, so every wallet joins the referral tree as a leaf and no cycle of any length can form.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn credit_referral(
+    config: &mut GlobalConfig,
+    buyer_record: &mut PurchaseRecord,
+    referrer: &mut ReferrerAccount,
+    referrer_record: &AccountInfo,
+    buyer_referrer: &AccountInfo,
+    amount_xls: u64,
+    lxr_paid: u64,
+    referral_lxr: u64,
+) -> Result<()> {
+    let buyer = buyer_record.owner;
+    require!(referrer.owner != buyer, crate::ErrorCode::SelfReferral);
+
+    let (expected_record, _) = Pubkey::find_program_address(
+        &[b"purchase_record", referrer.owner.as_ref()],
+        &crate::ID,
+    );
+    require!(referrer_record.key() == expected_record, crate::ErrorCode::InvalidReferrerRecord);
+    if !referrer_record.data_is_empty() {
+        let data = referrer_record.try_borrow_data()?;
+        let record = PurchaseRecord::try_deserialize(&mut &data[..])?;
+        require!(record.referrer != buyer, crate::ErrorCode::CircularReferral);
+    }
+
+    // The first referrer sticks to the buyer
+    if buyer_record.referrer == Pubkey::default() {
+        let (expected_referrer, _) = Pubkey::find_program_address(&[b"referrer", buyer.as_ref()], &crate::ID);
+        require!(buyer_referrer.key() == expected_referrer, crate::ErrorCode::InvalidBuyerReferrer);
+        if !buyer_referrer.data_is_empty() {
+            let data = buyer_referrer.try_borrow_data()?;
+            let account = ReferrerAccount::try_deserialize(&mut &data[..])?;
+            require!(account.referred_xls == 0, crate::ErrorCode::BuyerHasReferrals);
+        }
+        buyer_record.referrer = referrer.owner;
+    }
+    require!(buyer_record.referrer == referrer.owner, crate::ErrorCode::ReferrerMismatch);
+
+    referrer.pending_lxr = referrer.pending_lxr.safe_add(referral_lxr)?;
+    referrer.referred_xls = referrer.referred_xls.safe_add(amount_xls)?;
+    referrer.referred_lxr = referrer.referred_lxr.safe_add(lxr_paid)?;
+    config.referral_lxr_reserved = config.referral_lxr_reserved.safe_add(referral_lxr)?;
+
+    emit!(ReferralCredited {
+        referrer: referrer.owner,
+        buyer,
+        amount_xls,
+        lxr_credited: referral_lxr,
+    });
+    Ok(())
+}
+
+pub fn register_referrer_handler(ctx: Context<RegisterReferrer>) -> Result<()> {
+    let referrer = &mut ctx.accounts.referrer_account;
+    referrer.owner = ctx.accounts.user.key();
+    referrer.pending_lxr = 0;
+    referrer.total_claimed = 0;
+    referrer.referred_xls = 0;
+    referrer.referred_lxr = 0;
+    referrer.bump = ctx.bumps.referrer_account;
+
+    emit!(ReferrerRegistered { referrer: referrer.owner });
+
+    msg!("Referrer Registered: {}", referrer.owner);
+    Ok(())
+}
+
+pub fn claim_referral_handler(ctx: Context<ClaimReferral>) -> Result<()> {
+    let pending = ctx.accounts.referrer_account.pending_lxr;
+    require!(pending > 0, crate::ErrorCode::NoReferralRewards);
+
+    // Never grossed up: only `pending` is reserved in the RWA Vault
+    let payout = fee_adjusted(&ctx.accounts.lxr_mint, pending, false)?;
+    require!(ctx.accounts.rwa_vault_lxr.amount >= payout.send, crate::ErrorCode::VaultInsufficient);
+
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let transfer_lxr = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.rwa_vault_lxr.to_account_info(),
+            mint: ctx.accounts.lxr_mint.to_account_info(),
+            to: ctx.accounts.user_lxr_account.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer_lxr, payout.send, ctx.accounts.lxr_mint.decimals)?;
+
+    let config = &mut ctx.accounts.global_config;
+    config.referral_lxr_reserved = config.referral_lxr_reserved.safe_sub(pending)?;
+
+    let referrer = &mut ctx.accounts.referrer_account;
+    referrer.pending_lxr = 0;
+    referrer.total_claimed = referrer.total_claimed.safe_add(pending)?;
+
+    emit!(ReferralClaimed {
+        referrer: referrer.owner,
+        amount: payout.received,
+        total_claimed: referrer.total_claimed,
+    });
+
+    msg!("Referral Claimed: {} LXR", payout.received);
+    Ok(())
+}
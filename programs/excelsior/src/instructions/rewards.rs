@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 use anchor_lang::solana_program::keccak;
+use crate::math::SafeMath;
+use crate::events::*;
 
 #[derive(Accounts)]
 pub struct InitDistributor<'info> {
@@ -77,10 +79,18 @@ pub fn init_distributor_handler(ctx: Context<InitDistributor>, root: [u8; 32]) -
     distributor.mint = ctx.accounts.mint.key();
     distributor.total_claimed = 0;
     distributor.bump = ctx.bumps.distributor;
+    
+    emit!(DistributorInitialized {
+        root,
+        mint: distributor.mint,
+    });
     Ok(())
 }
 
 pub fn claim_handler(ctx: Context<ClaimReward>, index: u64, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+    require!(amount > 0, crate::ErrorCode::ZeroAmount);
+    require!(ctx.accounts.distributor_vault.amount >= amount, crate::ErrorCode::VaultInsufficient);
+    
     // Verify Merkle Proof
     // Leaf = Keccak(index, claimant_pubkey, amount)
     let leaf = keccak::hashv(&[
@@ -101,7 +111,7 @@ pub fn claim_handler(ctx: Context<ClaimReward>, index: u64, amount: u64, proof:
     claim_status.claimant = ctx.accounts.user.key();
     
     // Update total claimed
-    ctx.accounts.distributor.total_claimed += amount;
+    ctx.accounts.distributor.total_claimed = ctx.accounts.distributor.total_claimed.safe_add(amount)?;
     
     // Transfer tokens
     let seeds = &[b"distributor".as_ref(), &[ctx.accounts.distributor.bump]];
@@ -119,6 +129,13 @@ pub fn claim_handler(ctx: Context<ClaimReward>, index: u64, amount: u64, proof:
     );
     token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.mint.decimals)?;
     
+    emit!(MerkleClaimed {
+        claimant: ctx.accounts.user.key(),
+        index,
+        amount,
+        mint: ctx.accounts.mint.key(),
+    });
+    
     msg!("Claimed {} tokens. Index: {}", amount, index);
     Ok(())
 }
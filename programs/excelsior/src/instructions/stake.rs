@@ -1,6 +1,11 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_2022;
 use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 use crate::state::*;
+use crate::transfer_fee::fee_adjusted;
+use crate::pricing;
+use crate::math::*;
+use crate::events::*;
 
 #[derive(Accounts)]
 pub struct InitUser<'info> {
@@ -19,6 +24,25 @@ pub struct InitUser<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct MigrateUserAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>, // Anyone may migrate; the payer funds the extra rent
+    
+    /// CHECK: Pre-upgrade layout that Account<UserAccount> cannot deserialize; PDA and owner are checked here,
+    /// the discriminator in the handler
+    #[account(
+        mut,
+        seeds = [b"user_account", user.as_ref()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub user_account: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct StakeXls<'info> {
     #[account(mut)]
@@ -39,22 +63,41 @@ pub struct StakeXls<'info> {
     pub user_account: Account<'info, UserAccount>,
     
     // User Accounts
-    #[account(mut)]
-    pub user_xls_account: InterfaceAccount<'info, TokenAccount>, // Deposit Source
-    #[account(mut)]
-    pub user_lxr_account: InterfaceAccount<'info, TokenAccount>, // Reward Dest
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>, // Deposit Source
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>, // Reward Dest
     
     // Vaults
-    #[account(mut)]
-    pub xls_vault_staking: InterfaceAccount<'info, TokenAccount>, // Deposit Dest
-    #[account(mut, address = global_config.lxr_vault_rewards)]
-    pub lxr_vault_rewards: InterfaceAccount<'info, TokenAccount>, // Reward Source (LXR)
+    #[account(
+        mut,
+        address = global_config.xls_vault_staking @ crate::ErrorCode::InvalidStakingVault,
+        constraint = xls_vault_staking.mint == xls_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = xls_vault_staking.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub xls_vault_staking: Box<InterfaceAccount<'info, TokenAccount>>, // Deposit Dest
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = lxr_vault_rewards.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>, // Reward Source (LXR)
     
-    #[account(address = global_config.xls_mint)]
-    pub xls_mint: InterfaceAccount<'info, Mint>,
-    #[account(address = global_config.lxr_mint)]
-    pub lxr_mint: InterfaceAccount<'info, Mint>,
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
     
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
     pub token_program: Interface<'info, TokenInterface>,
 }
 
@@ -77,64 +120,346 @@ pub struct UnstakeXls<'info> {
     )]
     pub user_account: Account<'info, UserAccount>,
     
-    #[account(mut)]
-    pub user_xls_account: InterfaceAccount<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_lxr_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
     
-    #[account(mut)]
-    pub xls_vault_staking: InterfaceAccount<'info, TokenAccount>,
-    #[account(mut, address = global_config.lxr_vault_rewards)]
-    pub lxr_vault_rewards: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = global_config.xls_vault_staking @ crate::ErrorCode::InvalidStakingVault,
+        constraint = xls_vault_staking.mint == xls_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = xls_vault_staking.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub xls_vault_staking: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = lxr_vault_rewards.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_account", user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>, // Default Reward Dest
+    #[account(
+        mut,
+        constraint = destination.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+    )]
+    pub destination: Option<Box<InterfaceAccount<'info, TokenAccount>>>, // Optional: pay rewards to any LXR account instead
+    
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = lxr_vault_rewards.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct ExpireLock<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_account", user.as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>, // Staker's Reward Dest
+    
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = lxr_vault_rewards.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct LockStake<'info> {
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_account", user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>, // Reward Dest
+    
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = lxr_vault_rewards.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawUnbonded<'info> {
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_account", user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
     
-    #[account(address = global_config.xls_mint)]
-    pub xls_mint: InterfaceAccount<'info, Mint>,
-    #[account(address = global_config.lxr_mint)]
-    pub lxr_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        address = global_config.xls_vault_staking @ crate::ErrorCode::InvalidStakingVault,
+        constraint = xls_vault_staking.mint == xls_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = xls_vault_staking.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub xls_vault_staking: Box<InterfaceAccount<'info, TokenAccount>>,
     
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
     pub token_program: Interface<'info, TokenInterface>,
 }
 
+#[derive(Accounts)]
+pub struct CancelUnbonding<'info> {
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_account", user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>, // Reward Dest
+    
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = lxr_vault_rewards.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Lock periods a staker can commit to in exchange for a boosted reward weight.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum StakeLock {
+    ThreeMonths,
+    SixMonths,
+    TwelveMonths,
+    TwentyFourMonths,
+}
+
+impl StakeLock {
+    pub fn duration(self) -> i64 {
+        let days = match self {
+            StakeLock::ThreeMonths => 90,
+            StakeLock::SixMonths => 180,
+            StakeLock::TwelveMonths => 365,
+            StakeLock::TwentyFourMonths => 730,
+        };
+        days * 86_400
+    }
+
+    pub fn boost_bps(self) -> u16 {
+        match self {
+            StakeLock::ThreeMonths => 12_500,
+            StakeLock::SixMonths => 15_000,
+            StakeLock::TwelveMonths => 20_000,
+            StakeLock::TwentyFourMonths => 30_000,
+        }
+    }
+}
+
 pub fn init_user_handler(ctx: Context<InitUser>) -> Result<()> {
     let user_acct = &mut ctx.accounts.user_account;
     user_acct.owner = ctx.accounts.user.key();
     user_acct.staked_xls = 0;
     user_acct.reward_debt = 0;
+    user_acct.total_claimed = 0;
+    user_acct.effective_stake = 0;
+    user_acct.lock_end = 0;
+    user_acct.lock_boost_bps = BPS_DENOMINATOR as u16;
+    user_acct.unbonding_xls = 0;
+    user_acct.unbonding_end = 0;
     user_acct.bump = ctx.bumps.user_account;
+    
+    emit!(UserInitialized { user: user_acct.owner });
+    Ok(())
+}
+
+/// Grows a UserAccount created before the appended fields and fills them in. Existing stakes
+/// count at 1x, the same assumption upgrade_config makes when it seeds total_effective_stake.
+pub fn migrate_user_account_handler(ctx: Context<MigrateUserAccount>, _user: Pubkey) -> Result<()> {
+    let info = ctx.accounts.user_account.to_account_info();
+    require!(info.data_len() < UserAccount::LEN, crate::ErrorCode::UserAccountCurrent);
+    
+    let required = Rent::get()?.minimum_balance(UserAccount::LEN);
+    let top_up = required.saturating_sub(info.lamports());
+    if top_up > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    info.realloc(UserAccount::LEN, true)?;
+    
+    let mut user_acct = UserAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    if user_acct.lock_boost_bps == 0 {
+        user_acct.effective_stake = user_acct.staked_xls;
+        user_acct.lock_boost_bps = BPS_DENOMINATOR as u16;
+    }
+    user_acct.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    
+    emit!(UserAccountMigrated {
+        user: user_acct.owner,
+        effective_stake: user_acct.effective_stake,
+    });
+    msg!("Migrated user account of {}", user_acct.owner);
     Ok(())
 }
 
 pub fn stake_handler(ctx: Context<StakeXls>, amount: u64) -> Result<()> {
     let global_config = &mut ctx.accounts.global_config;
     let user_acct = &mut ctx.accounts.user_account;
+    let now = Clock::get()?.unix_timestamp;
+    pricing::accrue_emissions(global_config, now)?;
     
     // 1. Update Rewards (Claim Pending LXR)
-    let acc_rewards = global_config.acc_rewards_per_share;
-    if user_acct.staked_xls > 0 {
-        let pending = (user_acct.staked_xls as u128)
-            .checked_mul(acc_rewards).unwrap()
-            .checked_div(1_000_000_000_000).unwrap()
-            .checked_sub(user_acct.reward_debt).unwrap();
-            
-        if pending > 0 {
-            // Transfer LXR from Reward Vault -> User
-            let seeds = &[b"global_config".as_ref(), &[global_config.bump]];
-            let signer = &[&seeds[..]];
-            
-            let transfer_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.lxr_vault_rewards.to_account_info(),
-                    mint: ctx.accounts.lxr_mint.to_account_info(),
-                    to: ctx.accounts.user_lxr_account.to_account_info(),
-                    authority: global_config.to_account_info(),
-                },
-                signer
-            );
-            token_interface::transfer_checked(transfer_ctx, pending as u64, ctx.accounts.lxr_mint.decimals)?;
-            msg!("Claimed {} LXR pending rewards", pending);
-        }
-    }
+    settle_rewards(
+        global_config,
+        user_acct,
+        &ctx.accounts.lxr_vault_rewards,
+        &ctx.accounts.lxr_mint,
+        &ctx.accounts.user_lxr_account,
+        &ctx.accounts.token_program,
+    )?;
     
     // 2. Transfer Staked XLS from User to Vault
     if amount > 0 {
@@ -149,14 +474,22 @@ pub fn stake_handler(ctx: Context<StakeXls>, amount: u64) -> Result<()> {
         );
         token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.xls_mint.decimals)?;
         
-        user_acct.staked_xls = user_acct.staked_xls.checked_add(amount).unwrap();
-        global_config.total_staked_xls = global_config.total_staked_xls.checked_add(amount).unwrap();
+        user_acct.staked_xls = user_acct.staked_xls.safe_add(amount)?;
+        global_config.total_staked_xls = global_config.total_staked_xls.safe_add(amount)?;
+        
+        emit!(XlsStaked {
+            user: ctx.accounts.user.key(),
+            amount,
+            user_staked: user_acct.staked_xls,
+            total_staked_xls: global_config.total_staked_xls,
+        });
     }
     
-    // 3. Update Reward Debt
-    user_acct.reward_debt = (user_acct.staked_xls as u128)
-        .checked_mul(acc_rewards).unwrap()
-        .checked_div(1_000_000_000_000).unwrap();
+    // 3. Update Effective Stake and Reward Debt
+    pricing::rebase_stake(global_config, user_acct, now)?;
+    
+    // 4. Release rewards parked while nobody was staked (after the debt, so this stake earns them)
+    pricing::credit_stakers(global_config, 0)?;
         
     Ok(())
 }
@@ -166,54 +499,354 @@ pub fn unstake_handler(ctx: Context<UnstakeXls>, amount: u64) -> Result<()> {
     let user_acct = &mut ctx.accounts.user_account;
     
     require!(user_acct.staked_xls >= amount, crate::ErrorCode::InsufficientFunds);
+    let now = Clock::get()?.unix_timestamp;
+    require!(amount == 0 || now >= user_acct.lock_end, crate::ErrorCode::StakeLocked);
+    pricing::accrue_emissions(global_config, now)?;
     
     // 1. Update Rewards (Claim Pending LXR)
-    let acc_rewards = global_config.acc_rewards_per_share;
-    let pending = (user_acct.staked_xls as u128)
-        .checked_mul(acc_rewards).unwrap()
-        .checked_div(1_000_000_000_000).unwrap()
-        .checked_sub(user_acct.reward_debt).unwrap();
-        
-    let seeds = &[b"global_config".as_ref(), &[global_config.bump]];
-    let signer = &[&seeds[..]];
-
-    if pending > 0 {
-         let transfer_ctx = CpiContext::new_with_signer(
-             ctx.accounts.token_program.to_account_info(),
-             TransferChecked {
-                 from: ctx.accounts.lxr_vault_rewards.to_account_info(),
-                 mint: ctx.accounts.lxr_mint.to_account_info(),
-                 to: ctx.accounts.user_lxr_account.to_account_info(),
-                 authority: global_config.to_account_info(),
-             },
-             signer
-         );
-         token_interface::transfer_checked(transfer_ctx, pending as u64, ctx.accounts.lxr_mint.decimals)?;
-         msg!("Claimed {} LXR rewards", pending);
-    }
+    settle_rewards(
+        global_config,
+        user_acct,
+        &ctx.accounts.lxr_vault_rewards,
+        &ctx.accounts.lxr_mint,
+        &ctx.accounts.user_lxr_account,
+        &ctx.accounts.token_program,
+    )?;
     
-    // 2. Transfer Staked XLS back to User
+    // 2. Transfer Staked XLS back to User, or hold it for the unbonding period
     if amount > 0 {
-         let transfer_ctx = CpiContext::new_with_signer(
-             ctx.accounts.token_program.to_account_info(),
-             TransferChecked {
-                 from: ctx.accounts.xls_vault_staking.to_account_info(),
-                 mint: ctx.accounts.xls_mint.to_account_info(),
-                 to: ctx.accounts.user_xls_account.to_account_info(),
-                 authority: global_config.to_account_info(),
-             },
-             signer
-         );
-         token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.xls_mint.decimals)?;
-         
-         user_acct.staked_xls = user_acct.staked_xls.checked_sub(amount).unwrap();
-         global_config.total_staked_xls = global_config.total_staked_xls.checked_sub(amount).unwrap();
+        if global_config.unbonding_period == 0 {
+            release_staked_xls(
+                global_config,
+                &ctx.accounts.xls_vault_staking,
+                &ctx.accounts.xls_mint,
+                &ctx.accounts.user_xls_account,
+                &ctx.accounts.token_program,
+                amount,
+            )?;
+        } else {
+            // Leaves the stake (and stops earning) now; withdraw_unbonded releases it later.
+            // One unbonding balance at a time, so a new unstake can never push back an earlier release.
+            require!(user_acct.unbonding_xls == 0, crate::ErrorCode::AlreadyUnbonding);
+            user_acct.unbonding_xls = amount;
+            user_acct.unbonding_end = now.safe_add(global_config.unbonding_period)?;
+            global_config.total_unbonding_xls = global_config.total_unbonding_xls.safe_add(amount)?;
+            
+            emit!(XlsUnbonding {
+                user: ctx.accounts.user.key(),
+                amount,
+                unbonding_xls: user_acct.unbonding_xls,
+                unbonding_end: user_acct.unbonding_end,
+            });
+        }
+        
+        user_acct.staked_xls = user_acct.staked_xls.safe_sub(amount)?;
+        global_config.total_staked_xls = global_config.total_staked_xls.safe_sub(amount)?;
+        
+        emit!(XlsUnstaked {
+            user: ctx.accounts.user.key(),
+            amount,
+            user_staked: user_acct.staked_xls,
+            total_staked_xls: global_config.total_staked_xls,
+        });
     }
     
-    // 3. Update Reward Debt
-    user_acct.reward_debt = (user_acct.staked_xls as u128)
-        .checked_mul(acc_rewards).unwrap()
-        .checked_div(1_000_000_000_000).unwrap();
+    // 3. Update Effective Stake and Reward Debt
+    pricing::rebase_stake(global_config, user_acct, now)?;
         
     Ok(())
 }
+
+pub fn claim_rewards_handler(ctx: Context<ClaimRewards>) -> Result<()> {
+    let global_config = &mut ctx.accounts.global_config;
+    let user_acct = &mut ctx.accounts.user_account;
+    let now = Clock::get()?.unix_timestamp;
+    pricing::accrue_emissions(global_config, now)?;
+    
+    let destination = ctx.accounts.destination.as_ref().unwrap_or(&ctx.accounts.user_lxr_account);
+    let paid = settle_rewards(
+        global_config,
+        user_acct,
+        &ctx.accounts.lxr_vault_rewards,
+        &ctx.accounts.lxr_mint,
+        destination,
+        &ctx.accounts.token_program,
+    )?;
+    require!(paid > 0, crate::ErrorCode::NoStakingRewards);
+    
+    pricing::rebase_stake(global_config, user_acct, now)?;
+    
+    Ok(())
+}
+
+pub fn withdraw_unbonded_handler(ctx: Context<WithdrawUnbonded>) -> Result<()> {
+    let user_acct = &mut ctx.accounts.user_account;
+    let amount = user_acct.unbonding_xls;
+    require!(amount > 0, crate::ErrorCode::NothingUnbonding);
+    require!(Clock::get()?.unix_timestamp >= user_acct.unbonding_end, crate::ErrorCode::StillUnbonding);
+    
+    release_staked_xls(
+        &ctx.accounts.global_config,
+        &ctx.accounts.xls_vault_staking,
+        &ctx.accounts.xls_mint,
+        &ctx.accounts.user_xls_account,
+        &ctx.accounts.token_program,
+        amount,
+    )?;
+    
+    user_acct.unbonding_xls = 0;
+    let global_config = &mut ctx.accounts.global_config;
+    global_config.total_unbonding_xls = global_config.total_unbonding_xls.safe_sub(amount)?;
+    
+    emit!(UnbondedWithdrawn {
+        user: user_acct.owner,
+        amount,
+    });
+    msg!("Withdrew {} unbonded XLS", amount);
+    
+    Ok(())
+}
+
+/// Moves the whole unbonding balance back into the stake, where it earns again from now on.
+pub fn cancel_unbonding_handler(ctx: Context<CancelUnbonding>) -> Result<()> {
+    let global_config = &mut ctx.accounts.global_config;
+    let user_acct = &mut ctx.accounts.user_account;
+    let amount = user_acct.unbonding_xls;
+    require!(amount > 0, crate::ErrorCode::NothingUnbonding);
+    let now = Clock::get()?.unix_timestamp;
+    pricing::accrue_emissions(global_config, now)?;
+    
+    settle_rewards(
+        global_config,
+        user_acct,
+        &ctx.accounts.lxr_vault_rewards,
+        &ctx.accounts.lxr_mint,
+        &ctx.accounts.user_lxr_account,
+        &ctx.accounts.token_program,
+    )?;
+    
+    user_acct.unbonding_xls = 0;
+    user_acct.unbonding_end = 0;
+    user_acct.staked_xls = user_acct.staked_xls.safe_add(amount)?;
+    global_config.total_unbonding_xls = global_config.total_unbonding_xls.safe_sub(amount)?;
+    global_config.total_staked_xls = global_config.total_staked_xls.safe_add(amount)?;
+    pricing::rebase_stake(global_config, user_acct, now)?;
+    pricing::credit_stakers(global_config, 0)?;
+    
+    emit!(UnbondingCancelled {
+        user: user_acct.owner,
+        amount,
+        user_staked: user_acct.staked_xls,
+        total_staked_xls: global_config.total_staked_xls,
+    });
+    msg!("Restaked {} unbonding XLS", amount);
+    
+    Ok(())
+}
+
+/// Permissionless crank: drops an expired lock back to 1x so an idle staker stops diluting everyone else.
+/// Emission is settled only up to lock_end at the boosted weight; the rest accrues at 1x from there.
+pub fn expire_lock_handler(ctx: Context<ExpireLock>, _user: Pubkey) -> Result<()> {
+    let global_config = &mut ctx.accounts.global_config;
+    let user_acct = &mut ctx.accounts.user_account;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now >= user_acct.lock_end && user_acct.lock_boost_bps as u64 > BPS_DENOMINATOR,
+        crate::ErrorCode::LockNotExpired
+    );
+    pricing::accrue_emissions(global_config, user_acct.lock_end)?;
+    
+    settle_rewards(
+        global_config,
+        user_acct,
+        &ctx.accounts.lxr_vault_rewards,
+        &ctx.accounts.lxr_mint,
+        &ctx.accounts.user_lxr_account,
+        &ctx.accounts.token_program,
+    )?;
+    pricing::rebase_stake(global_config, user_acct, now)?;
+    
+    emit!(StakeLockUpdated {
+        user: user_acct.owner,
+        lock_end: user_acct.lock_end,
+        boost_bps: user_acct.lock_boost_bps,
+        effective_stake: user_acct.effective_stake,
+        total_effective_stake: global_config.total_effective_stake,
+    });
+    msg!("Expired stake lock of {}", user_acct.owner);
+    
+    Ok(())
+}
+
+/// Locks the whole stake until now + the chosen period for a boosted reward weight.
+/// Rewards up to now settle at the old weight. A lock can be extended but never end earlier.
+pub fn lock_stake_handler(ctx: Context<LockStake>, lock: StakeLock) -> Result<()> {
+    let global_config = &mut ctx.accounts.global_config;
+    let user_acct = &mut ctx.accounts.user_account;
+    let now = Clock::get()?.unix_timestamp;
+    pricing::accrue_emissions(global_config, now)?;
+    
+    let lock_end = now.safe_add(lock.duration())?;
+    require!(lock_end >= user_acct.lock_end, crate::ErrorCode::LockShortened);
+    
+    settle_rewards(
+        global_config,
+        user_acct,
+        &ctx.accounts.lxr_vault_rewards,
+        &ctx.accounts.lxr_mint,
+        &ctx.accounts.user_lxr_account,
+        &ctx.accounts.token_program,
+    )?;
+    
+    user_acct.lock_end = lock_end;
+    user_acct.lock_boost_bps = lock.boost_bps();
+    pricing::rebase_stake(global_config, user_acct, now)?;
+    
+    emit!(StakeLockUpdated {
+        user: user_acct.owner,
+        lock_end,
+        boost_bps: user_acct.lock_boost_bps,
+        effective_stake: user_acct.effective_stake,
+        total_effective_stake: global_config.total_effective_stake,
+    });
+    msg!("Stake locked until {} at {} bps", lock_end, user_acct.lock_boost_bps);
+    
+    Ok(())
+}
+
+/// Pays `user_acct` everything pending at the current acc_rewards_per_share and adds it to total_claimed.
+/// Callers accrue emissions first and rebase the stake once staked_xls and the lock are final. Returns the LXR settled.
+fn settle_rewards<'info>(
+    global_config: &Account<'info, GlobalConfig>,
+    user_acct: &mut UserAccount,
+    lxr_vault_rewards: &InterfaceAccount<'info, TokenAccount>,
+    lxr_mint: &InterfaceAccount<'info, Mint>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<u64> {
+    let pending = user_acct.pending_rewards(global_config.acc_rewards_per_share)?;
+    if pending == 0 {
+        return Ok(0);
+    }
+    
+    // LXR Transfer Fee: destination nets pending - fee. Never grossed up: the accumulator only
+    // backs `pending`, so sending more would leave the Reward Vault short for later claimers.
+    let payout = fee_adjusted(lxr_mint, pending, false)?;
+    require!(lxr_vault_rewards.amount >= payout.send, crate::ErrorCode::VaultInsufficient);
+    
+    // Transfer LXR from Reward Vault -> Destination
+    let seeds = &[b"global_config".as_ref(), &[global_config.bump]];
+    let signer = &[&seeds[..]];
+    
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TransferChecked {
+            from: lxr_vault_rewards.to_account_info(),
+            mint: lxr_mint.to_account_info(),
+            to: destination.to_account_info(),
+            authority: global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer_ctx, payout.send, lxr_mint.decimals)?;
+    
+    user_acct.total_claimed = user_acct.total_claimed.safe_add(pending)?;
+    emit!(RewardsPaid {
+        user: user_acct.owner,
+        destination: destination.key(),
+        amount: payout.received,
+        total_claimed: user_acct.total_claimed,
+    });
+    msg!("Claimed {} LXR pending rewards", payout.received);
+    
+    Ok(pending)
+}
+
+/// Sends XLS held for a staker from the Staking Vault back to them.
+fn release_staked_xls<'info>(
+    global_config: &Account<'info, GlobalConfig>,
+    xls_vault_staking: &InterfaceAccount<'info, TokenAccount>,
+    xls_mint: &InterfaceAccount<'info, Mint>,
+    user_xls_account: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[b"global_config".as_ref(), &[global_config.bump]];
+    let signer = &[&seeds[..]];
+    
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        TransferChecked {
+            from: xls_vault_staking.to_account_info(),
+            mint: xls_mint.to_account_info(),
+            to: user_xls_account.to_account_info(),
+            authority: global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer_ctx, amount, xls_mint.decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::{accrue_emissions, rebase_stake, circulating_xls};
+
+    #[test]
+    fn lock_boost_weights_stake_until_expiry() {
+        let mut config = GlobalConfig { total_effective_stake: 100, acc_rewards_per_share: REWARD_PRECISION, ..Default::default() };
+        let mut user = UserAccount {
+            staked_xls: 40,
+            lock_end: 1_000,
+            lock_boost_bps: 15_000,
+            ..Default::default()
+        };
+
+        rebase_stake(&mut config, &mut user, 500).unwrap();
+        assert_eq!((user.effective_stake, config.total_effective_stake), (60, 160));
+        assert_eq!(user.reward_debt, 60);
+
+        // Past lock_end the boost lapses back to 1x
+        rebase_stake(&mut config, &mut user, 1_000).unwrap();
+        assert_eq!((user.effective_stake, config.total_effective_stake), (40, 140));
+        assert_eq!(user.lock_boost_bps, 10_000);
+    }
+
+    #[test]
+    fn expired_lock_stops_boosting_from_lock_end() {
+        // A (40 XLS at 2x until t=100) sits idle next to B (40 XLS at 1x); 1 LXR/s is emitted
+        let mut config = GlobalConfig {
+            reward_rate_per_second: 1,
+            emission_end_ts: 1_000,
+            emission_budget: 1_000,
+            total_effective_stake: 120,
+            ..Default::default()
+        };
+        let mut idle = UserAccount { staked_xls: 40, effective_stake: 80, lock_end: 100, lock_boost_bps: 20_000, ..Default::default() };
+
+        // The crank runs at t=220: emission up to lock_end splits 2:1, later emission 1:1
+        accrue_emissions(&mut config, idle.lock_end).unwrap();
+        let at_expiry = idle.pending_rewards(config.acc_rewards_per_share).unwrap();
+        rebase_stake(&mut config, &mut idle, 220).unwrap();
+        assert_eq!(config.total_effective_stake, 80);
+        accrue_emissions(&mut config, 220).unwrap();
+
+        assert_eq!(at_expiry, 66); // 100 * 80 / 120, rounded down
+        assert_eq!(idle.pending_rewards(config.acc_rewards_per_share).unwrap(), 60); // 120 * 40 / 80
+    }
+
+    #[test]
+    fn unbonding_xls_stays_out_of_circulation() {
+        let config = GlobalConfig {
+            xls_vault_staking: Pubkey::new_unique(),
+            total_staked_xls: 100,
+            total_unbonding_xls: 50,
+            ..Default::default()
+        };
+        assert_eq!(circulating_xls(&config, 1_000, 400).unwrap(), 450);
+
+        // A shared vault already holds both in its balance
+        let shared = GlobalConfig { xls_vault_staking: config.xls_vault_supply, ..config.clone() };
+        assert_eq!(circulating_xls(&shared, 1_000, 550).unwrap(), 450);
+    }
+}
@@ -1,6 +1,12 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_2022;
 use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, Burn};
 use crate::state::*;
+use crate::pricing::{self, MintDecimals};
+use crate::transfer_fee::fee_adjusted;
+use crate::math::{SafeMath, bps_of};
+use crate::events::*;
+use crate::instructions::referral;
 
 #[derive(Accounts)]
 pub struct BuyXls<'info> {
@@ -14,25 +20,62 @@ pub struct BuyXls<'info> {
     )]
     pub global_config: Account<'info, GlobalConfig>,
     
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"purchase_record", user.key().as_ref()],
+        bump,
+        space = PurchaseRecord::LEN
+    )]
+    pub purchase_record: Box<Account<'info, PurchaseRecord>>,
+    
     // User Accounts
-    #[account(mut)]
-    pub user_lxr_account: InterfaceAccount<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_xls_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
     
     // Vaults
-    #[account(mut)] // Contract Vault holding XLS for sale
-    pub xls_vault_supply: InterfaceAccount<'info, TokenAccount>,
-    #[account(mut)] // RWA Vault receiving 70% LXR
-    pub rwa_vault_lxr: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault,
+        constraint = xls_vault_supply.mint == xls_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = xls_vault_supply.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )] // Contract Vault holding XLS for sale
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault,
+        constraint = rwa_vault_lxr.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = rwa_vault_lxr.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )] // RWA Vault receiving the vault share of LXR
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
     
     // Mints
-    #[account(address = global_config.xls_mint)]
-    pub xls_mint: InterfaceAccount<'info, Mint>,
-    #[account(mut, address = global_config.lxr_mint)]
-    pub lxr_mint: InterfaceAccount<'info, Mint>,
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
     
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
     pub token_program: Interface<'info, TokenInterface>, // Must be Token-2022
+    pub system_program: Program<'info, System>,
+    
+    // Referral (Optional)
+    #[account(mut)]
+    pub referrer_account: Option<Box<Account<'info, ReferrerAccount>>>,
+    /// CHECK: The referrer's PurchaseRecord PDA, verified in credit_referral (may not exist yet)
+    pub referrer_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: The buyer's own ReferrerAccount PDA, verified in credit_referral (may not exist)
+    pub buyer_referrer_account: Option<UncheckedAccount<'info>>,
 }
 
 #[derive(Accounts)]
@@ -45,39 +88,149 @@ pub struct RedeemXls<'info> {
         seeds = [b"global_config"],
         bump = global_config.bump,
     )]
-    pub global_config: Account<'info, GlobalConfig>,
+    pub global_config: Box<Account<'info, GlobalConfig>>,
     
-    #[account(mut)]
-    pub user_lxr_account: InterfaceAccount<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_xls_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"user_account", user.key().as_ref()],
+        bump = user_account.bump,
+    )] // Optional: stakers above the threshold pay the reduced exit fee
+    pub user_account: Option<Box<Account<'info, UserAccount>>>,
     
-    #[account(mut)]
-    pub xls_vault_supply: InterfaceAccount<'info, TokenAccount>,
-    #[account(mut)]
-    pub rwa_vault_lxr: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault,
+        constraint = xls_vault_supply.mint == xls_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = xls_vault_supply.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault,
+        constraint = rwa_vault_lxr.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = rwa_vault_lxr.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = global_config.lxr_vault_rewards @ crate::ErrorCode::InvalidRewardVault,
+        constraint = lxr_vault_rewards.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+    )] // Receives the exit fee
+    pub lxr_vault_rewards: Box<InterfaceAccount<'info, TokenAccount>>,
     
-    #[account(mut, address = global_config.xls_mint)]
-    pub xls_mint: InterfaceAccount<'info, Mint>,
-    #[account(address = global_config.lxr_mint)]
-    pub lxr_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
     
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
     pub token_program: Interface<'info, TokenInterface>,
 }
 
-pub fn buy_handler(ctx: Context<BuyXls>, amount_xls: u64) -> Result<()> {
-    // 1 XLS = 1,000,000 LXR
-    // User wants 'amount_xls'. Input in atomic units.
-    // If XLS decimals = 9 and LXR decimals = 9, then 1 XLS (1e9 units) costs 1M LXR (1e6 * 1e9 units).
-    // Ratio: 1,000,000 : 1
+/// What a `buy_xls` of `amount_xls` costs right now. Returned as-is by the `quote_buy` view.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BuyQuote {
+    pub lxr_paid: u64, // Leaves the buyer's wallet (Burn + Vault Transfer)
+    pub lxr_burned: u64,
+    pub vault_send: u64, // Passed to transfer_checked (includes gross-up)
+    pub lxr_to_vault: u64, // Credited to the RWA Vault after the transfer fee
+}
+
+/// What an instant `redeem_xls` of `amount_xls` pays right now. Returned as-is by the `quote_redeem` view.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RedeemQuote {
+    pub lxr_value: u64, // Redemption value before the exit fee
+    pub exit_fee: u64,
+    pub fee_to_stakers: u64, // Exit fee moved to the Reward Vault (0 while nobody is staked)
+    pub lxr_send: u64, // Passed to transfer_checked (includes gross-up)
+    pub lxr_out: u64, // Received by the user after the transfer fee
+}
+
+/// Shared by `buy_handler` and the `quote_buy` view so both use identical math.
+pub fn buy_quote(
+    config: &GlobalConfig,
+    xls_mint: &InterfaceAccount<Mint>,
+    lxr_mint: &InterfaceAccount<Mint>,
+    amount_xls: u64,
+) -> Result<BuyQuote> {
+    let decimals = MintDecimals { xls: xls_mint.decimals, lxr: lxr_mint.decimals };
+    let lxr_needed = pricing::quote_buy(config, amount_xls, decimals)?;
+    
+    // Default 30% Burn, 70% Vault (swap_burn_bps / swap_vault_bps)
+    let (burn_amount, vault_amount) = pricing::split_purchase(config, lxr_needed)?;
+    
+    // LXR Transfer Fee is withheld from the vault leg. With gross-up the buyer pays it on top.
+    let vault_transfer = fee_adjusted(lxr_mint, vault_amount, config.gross_up_transfer_fees)?;
+    Ok(BuyQuote {
+        lxr_paid: burn_amount.safe_add(vault_transfer.send)?,
+        lxr_burned: burn_amount,
+        vault_send: vault_transfer.send,
+        lxr_to_vault: vault_transfer.received,
+    })
+}
+
+/// Shared by `redeem_handler` and the `quote_redeem` view. `vault_lxr` is the free RWA Vault balance
+/// and `circulating` is measured before the redeemed XLS is burned.
+pub fn redeem_quote(
+    config: &GlobalConfig,
+    lxr_mint: &InterfaceAccount<Mint>,
+    decimals: MintDecimals,
+    vault_lxr: u64,
+    circulating: u64,
+    staked_xls: u64,
+    amount_xls: u64,
+) -> Result<RedeemQuote> {
+    // Fixed Mode: redemption_rate LXR per XLS (The "Book Value").
+    // NAV Mode: Share = (Amount XLS / Circulating XLS) * RWA Vault Balance, clamped to [floor, ceiling].
+    let lxr_value = pricing::redemption_value(config, amount_xls, vault_lxr, circulating, decimals)?;
+    
+    // Exit fee is carved out of the redemption and paid to stakers. With nobody staked it stays in the RWA Vault.
+    let exit_fee = bps_of(lxr_value, pricing::redemption_fee_bps(config, staked_xls) as u64)?;
+    let fee_to_stakers = if config.total_effective_stake > 0 { exit_fee } else { 0 };
     
-    let lxr_needed = amount_xls.checked_mul(1_000_000).ok_or(ProgramError::ArithmeticOverflow)?;
+    // The user's guarantee is on what actually arrives after the LXR Transfer Fee.
+    // Vault-funded, so never grossed up: the fee would come out of the remaining holders' NAV.
+    let lxr_transfer = fee_adjusted(lxr_mint, lxr_value.safe_sub(exit_fee)?, false)?;
+    Ok(RedeemQuote {
+        lxr_value,
+        exit_fee,
+        fee_to_stakers,
+        lxr_send: lxr_transfer.send,
+        lxr_out: lxr_transfer.received,
+    })
+}
+
+pub fn buy_handler(ctx: Context<BuyXls>, amount_xls: u64, max_lxr_in: u64, expires_at: i64) -> Result<()> {
+    require!(amount_xls > 0, crate::ErrorCode::ZeroAmount);
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= expires_at, crate::ErrorCode::QuoteExpired);
+    require!(ctx.accounts.xls_vault_supply.amount >= amount_xls, crate::ErrorCode::VaultInsufficient);
     
-    // 30% Burn, 70% Vault
-    let burn_amount = lxr_needed.checked_mul(30).unwrap().checked_div(100).unwrap();
-    let vault_amount = lxr_needed.checked_sub(burn_amount).unwrap(); // Rest enters vault
+    // Fixed Mode: 1 XLS = swap_price LXR (Default 1,000,000)
+    // Tiered Mode: Price steps up with total_xls_sold (see pricing::tiered_cost)
+    // User wants 'amount_xls'. Input in atomic units; prices are UI-unit ratios.
+    // e.g. XLS decimals = 6, LXR decimals = 9: 1 XLS (1e6 units) costs 1M LXR (1e6 * 1e9 units).
+    let BuyQuote { lxr_paid, lxr_burned: burn_amount, vault_send, lxr_to_vault } = buy_quote(
+        &ctx.accounts.global_config,
+        &ctx.accounts.xls_mint,
+        &ctx.accounts.lxr_mint,
+        amount_xls,
+    )?;
+    require!(lxr_paid <= max_lxr_in, crate::ErrorCode::MaxLxrInExceeded);
     
-    // 1. Transfer LXR to RWA Vault (70%)
+    // 1. Transfer LXR to RWA Vault
     let transfer_ctx = CpiContext::new(
         ctx.accounts.token_program.to_account_info(),
         TransferChecked {
@@ -87,9 +240,9 @@ pub fn buy_handler(ctx: Context<BuyXls>, amount_xls: u64) -> Result<()> {
             authority: ctx.accounts.user.to_account_info(),
         }
     );
-    token_interface::transfer_checked(transfer_ctx, vault_amount, ctx.accounts.lxr_mint.decimals)?;
+    token_interface::transfer_checked(transfer_ctx, vault_send, ctx.accounts.lxr_mint.decimals)?;
     
-    // 2. Burn LXR (30%)
+    // 2. Burn LXR
     let burn_ctx = CpiContext::new(
         ctx.accounts.token_program.to_account_info(),
         Burn {
@@ -101,7 +254,34 @@ pub fn buy_handler(ctx: Context<BuyXls>, amount_xls: u64) -> Result<()> {
     token_interface::burn(burn_ctx, burn_amount)?;
     
     // Update Stats
-    ctx.accounts.global_config.total_lxr_burned += burn_amount;
+    let config = &mut ctx.accounts.global_config;
+    let record = &mut ctx.accounts.purchase_record;
+    record.owner = ctx.accounts.user.key();
+    record.bump = ctx.bumps.purchase_record;
+    pricing::record_purchase(config, record, amount_xls, now)?;
+    config.total_lxr_burned = config.total_lxr_burned.safe_add(burn_amount)?;
+    config.total_xls_sold = config.total_xls_sold.safe_add(amount_xls)?;
+    
+    // Referral: part of the vault leg is reserved for the referrer inside the RWA Vault
+    if let Some(referrer) = ctx.accounts.referrer_account.as_deref_mut() {
+        let referrer_record = ctx.accounts.referrer_record
+            .as_ref()
+            .ok_or(crate::ErrorCode::InvalidReferrerRecord)?;
+        let buyer_referrer = ctx.accounts.buyer_referrer_account
+            .as_ref()
+            .ok_or(crate::ErrorCode::InvalidBuyerReferrer)?;
+        let referral_lxr = bps_of(lxr_to_vault, config.referral_bps as u64)?;
+        referral::credit_referral(
+            config,
+            record,
+            referrer,
+            referrer_record,
+            buyer_referrer,
+            amount_xls,
+            lxr_paid,
+            referral_lxr,
+        )?;
+    }
     
     // 3. Transfer XLS from Supply Vault to User
     // Use PDA Signer
@@ -120,12 +300,63 @@ pub fn buy_handler(ctx: Context<BuyXls>, amount_xls: u64) -> Result<()> {
     );
     token_interface::transfer_checked(transfer_xls, amount_xls, ctx.accounts.xls_mint.decimals)?;
     
-    msg!("Swap Successful: Paid {} LXR (Button 30%, Vault 70%), Received {} XLS", lxr_needed, amount_xls);
+    emit!(XlsPurchased {
+        buyer: ctx.accounts.user.key(),
+        amount_xls,
+        lxr_paid,
+        lxr_burned: burn_amount,
+        lxr_to_vault,
+        total_xls_sold: ctx.accounts.global_config.total_xls_sold,
+    });
+    
+    msg!(
+        "Swap Successful: Paid {} LXR (Burned {}, Vault Received {}), Received {} XLS",
+        lxr_paid, burn_amount, lxr_to_vault, amount_xls
+    );
     Ok(())
 }
 
-pub fn redeem_handler(ctx: Context<RedeemXls>, amount_xls: u64) -> Result<()> {
-    // 1. Burn User's XLS
+pub fn redeem_handler(ctx: Context<RedeemXls>, amount_xls: u64, min_lxr_out: u64, expires_at: i64) -> Result<()> {
+    require!(amount_xls > 0, crate::ErrorCode::ZeroAmount);
+    require!(Clock::get()?.unix_timestamp <= expires_at, crate::ErrorCode::QuoteExpired);
+    require!(!ctx.accounts.global_config.redemptions_halted, crate::ErrorCode::RedemptionsHalted);
+    
+    let decimals = MintDecimals {
+        xls: ctx.accounts.xls_mint.decimals,
+        lxr: ctx.accounts.lxr_mint.decimals,
+    };
+    // LXR reserved for settled queue tickets is not available to instant redemptions
+    let vault_lxr = pricing::free_vault_lxr(&ctx.accounts.global_config, ctx.accounts.rwa_vault_lxr.amount);
+    
+    // 1. Calculate LXR to return (before burning, the redeemed XLS is still circulating)
+    let circulating = pricing::circulating_xls(
+        &ctx.accounts.global_config,
+        ctx.accounts.xls_mint.supply,
+        ctx.accounts.xls_vault_supply.amount,
+    )?;
+    let config = &ctx.accounts.global_config;
+    let RedeemQuote { exit_fee, fee_to_stakers, lxr_send, lxr_out, .. } = redeem_quote(
+        config,
+        &ctx.accounts.lxr_mint,
+        decimals,
+        vault_lxr,
+        circulating,
+        ctx.accounts.user_account.as_ref().map_or(0, |account| account.staked_xls),
+        amount_xls,
+    )?;
+    let vault_outflow = lxr_send.safe_add(fee_to_stakers)?;
+    require!(lxr_out >= min_lxr_out, crate::ErrorCode::MinLxrOutNotMet);
+    require!(vault_lxr >= vault_outflow, crate::ErrorCode::VaultInsufficient);
+    
+    // Circuit Breaker: the vault must still cover the remaining circulating XLS at Book Value
+    pricing::require_coverage_after(
+        config,
+        vault_lxr.safe_sub(vault_outflow)?,
+        circulating.saturating_sub(amount_xls),
+        decimals,
+    )?;
+
+    // 2. Burn User's XLS
     let burn_ctx = CpiContext::new(
         ctx.accounts.token_program.to_account_info(),
         Burn {
@@ -136,16 +367,6 @@ pub fn redeem_handler(ctx: Context<RedeemXls>, amount_xls: u64) -> Result<()> {
     );
     token_interface::burn(burn_ctx, amount_xls)?;
     
-    // 2. Calculate LXR to return
-    // Redemption rate may vary, for now let's assume 1:1 backing value or fixed rate?
-    // User Spec said: "Redemption: If user returns 1 XLS, contract gives proportional value... from Reserve"
-    // For V1, let's assume we return the 70% that was put in initially (Guaranteed Floor).
-    // Or do we calculate based on Total Reserve / Total XLS?
-    // Let's implement Dynamic Redemption: Share = (Amount XLS / Total XLS Supply) * RWA Vault Balance
-    // NOTE: Simpler for now -> Return fixed ratio 700,000 LXR per XLS (The "Book Value").
-    
-    let lxr_to_return = amount_xls.checked_mul(700_000).unwrap();
-    
     // 3. Transfer LXR from RWA Vault to User
     let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
     let signer = &[&seeds[..]];
@@ -160,8 +381,246 @@ pub fn redeem_handler(ctx: Context<RedeemXls>, amount_xls: u64) -> Result<()> {
         },
         signer
     );
-    token_interface::transfer_checked(transfer_lxr, lxr_to_return, ctx.accounts.lxr_mint.decimals)?;
+    token_interface::transfer_checked(transfer_lxr, lxr_send, ctx.accounts.lxr_mint.decimals)?;
     
-    msg!("Redemption Successful: Burned {} XLS, Returned {} LXR", amount_xls, lxr_to_return);
+    // 4. Route the Exit Fee to the Reward Vault (same accounting as distribute_rent)
+    if fee_to_stakers > 0 {
+        let transfer_fee = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.rwa_vault_lxr.to_account_info(),
+                mint: ctx.accounts.lxr_mint.to_account_info(),
+                to: ctx.accounts.lxr_vault_rewards.to_account_info(),
+                authority: ctx.accounts.global_config.to_account_info(),
+            },
+            signer
+        );
+        token_interface::transfer_checked(transfer_fee, fee_to_stakers, ctx.accounts.lxr_mint.decimals)?;
+        
+        // Only what the Reward Vault actually received is owed to stakers
+        let fee_received = fee_adjusted(&ctx.accounts.lxr_mint, fee_to_stakers, false)?.received;
+        pricing::credit_stakers(&mut ctx.accounts.global_config, fee_received)?;
+    }
+    
+    emit!(XlsRedeemed {
+        user: ctx.accounts.user.key(),
+        amount_xls,
+        lxr_out,
+        mode: ctx.accounts.global_config.redemption_mode,
+        exit_fee,
+    });
+    
+    msg!(
+        "Redemption Successful: Burned {} XLS, Returned {} LXR (Exit Fee {} LXR)",
+        amount_xls, lxr_out, exit_fee
+    );
     Ok(())
 }
+
+#[derive(Accounts)]
+pub struct BuyXlsWith<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+    
+    #[account(
+        mut,
+        seeds = [b"payment_mint", payment_mint.key().as_ref()],
+        bump = payment_config.bump,
+    )]
+    pub payment_config: Box<Account<'info, PaymentMint>>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"purchase_record", user.key().as_ref()],
+        bump,
+        space = PurchaseRecord::LEN
+    )]
+    pub purchase_record: Box<Account<'info, PurchaseRecord>>,
+    
+    // User Accounts
+    #[account(
+        mut,
+        constraint = user_payment_account.mint == payment_mint.key() @ crate::ErrorCode::UserPaymentMintMismatch,
+        constraint = user_payment_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_payment_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    // Vaults
+    #[account(
+        mut,
+        address = payment_config.vault @ crate::ErrorCode::InvalidPaymentVault,
+    )] // Registered treasury for this payment mint
+    pub payment_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault,
+        constraint = xls_vault_supply.mint == xls_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = xls_vault_supply.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    // Mints
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub payment_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>, // XLS (Token-2022)
+    #[account(address = payment_config.token_program @ crate::ErrorCode::InvalidTokenProgram)]
+    pub payment_token_program: Interface<'info, TokenInterface>, // SPL Token or Token-2022
+    pub system_program: Program<'info, System>,
+}
+
+pub fn buy_with_handler(ctx: Context<BuyXlsWith>, amount_xls: u64, max_payment_in: u64, expires_at: i64) -> Result<()> {
+    require!(amount_xls > 0, crate::ErrorCode::ZeroAmount);
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= expires_at, crate::ErrorCode::QuoteExpired);
+    require!(ctx.accounts.payment_config.enabled, crate::ErrorCode::PaymentMintDisabled);
+    require!(ctx.accounts.xls_vault_supply.amount >= amount_xls, crate::ErrorCode::VaultInsufficient);
+    
+    // Fixed price per payment mint, e.g. XLS decimals = 9, USDC decimals = 6: 1 XLS (1e9 units) at 2 USDC costs 2e6 units.
+    let payment_needed = pricing::quote_buy_with(
+        ctx.accounts.payment_config.price,
+        amount_xls,
+        ctx.accounts.xls_mint.decimals,
+        ctx.accounts.payment_mint.decimals,
+    )?;
+    
+    let burn_amount = bps_of(payment_needed, ctx.accounts.payment_config.burn_bps as u64)?;
+    let vault_amount = payment_needed.safe_sub(burn_amount)?;
+    
+    // Token-2022 payment mints may carry a Transfer Fee; classic SPL mints pass through unchanged
+    let vault_transfer = fee_adjusted(
+        &ctx.accounts.payment_mint,
+        vault_amount,
+        ctx.accounts.global_config.gross_up_transfer_fees,
+    )?;
+    let amount_paid = burn_amount.safe_add(vault_transfer.send)?;
+    require!(amount_paid <= max_payment_in, crate::ErrorCode::MaxPaymentInExceeded);
+    
+    // 1. Transfer the retained share to the registered vault
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.payment_token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.user_payment_account.to_account_info(),
+            mint: ctx.accounts.payment_mint.to_account_info(),
+            to: ctx.accounts.payment_vault.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        }
+    );
+    token_interface::transfer_checked(transfer_ctx, vault_transfer.send, ctx.accounts.payment_mint.decimals)?;
+    
+    // 2. Burn per the mint's policy
+    if burn_amount > 0 {
+        let burn_ctx = CpiContext::new(
+            ctx.accounts.payment_token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.payment_mint.to_account_info(),
+                from: ctx.accounts.user_payment_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            }
+        );
+        token_interface::burn(burn_ctx, burn_amount)?;
+    }
+    
+    // Update Stats
+    let payment_config = &mut ctx.accounts.payment_config;
+    payment_config.total_received = payment_config.total_received.safe_add(vault_transfer.received)?;
+    let config = &mut ctx.accounts.global_config;
+    let record = &mut ctx.accounts.purchase_record;
+    record.owner = ctx.accounts.user.key();
+    record.bump = ctx.bumps.purchase_record;
+    pricing::record_purchase(config, record, amount_xls, now)?;
+    config.total_xls_sold = config.total_xls_sold.safe_add(amount_xls)?;
+    
+    // 3. Transfer XLS from Supply Vault to User
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+    
+    let transfer_xls = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.xls_vault_supply.to_account_info(),
+            mint: ctx.accounts.xls_mint.to_account_info(),
+            to: ctx.accounts.user_xls_account.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer_xls, amount_xls, ctx.accounts.xls_mint.decimals)?;
+    
+    emit!(XlsPurchasedWith {
+        buyer: ctx.accounts.user.key(),
+        payment_mint: ctx.accounts.payment_mint.key(),
+        amount_xls,
+        amount_paid,
+        amount_burned: burn_amount,
+        amount_to_vault: vault_transfer.received,
+        total_xls_sold: ctx.accounts.global_config.total_xls_sold,
+    });
+    
+    msg!(
+        "Swap Successful: Paid {} of {} (Burned {}, Vault Received {}), Received {} XLS",
+        amount_paid, ctx.accounts.payment_mint.key(), burn_amount, vault_transfer.received, amount_xls
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::{record_purchase, redemption_fee_bps};
+
+    #[test]
+    fn purchase_caps_roll_aligned_epochs() {
+        let mut config = GlobalConfig {
+            wallet_cap_xls: 150,
+            epoch_cap_xls: 100,
+            sale_epoch_duration: 10,
+            sale_epoch_start: 1_000,
+            ..Default::default()
+        };
+        let mut record = PurchaseRecord { owner: Pubkey::default(), total_purchased: 0, bump: 0, referrer: Pubkey::default() };
+
+        record_purchase(&mut config, &mut record, 100, 1_005).unwrap();
+        assert!(record_purchase(&mut config, &mut record, 1, 1_009).is_err()); // Epoch cap
+
+        // 25s later: two windows passed, the new one starts at 1_020
+        record_purchase(&mut config, &mut record, 50, 1_025).unwrap();
+        assert_eq!((config.sale_epoch_start, config.sale_epoch_sold), (1_020, 50));
+        assert!(record_purchase(&mut config, &mut record, 1, 1_025).is_err()); // Wallet cap
+        assert_eq!(record.total_purchased, 150);
+    }
+
+    #[test]
+    fn redemption_fee_tier_follows_stake() {
+        let config = GlobalConfig {
+            redemption_fee_bps: 100,
+            staker_redemption_fee_bps: 25,
+            staker_fee_threshold_xls: 1_000,
+            ..Default::default()
+        };
+        assert_eq!(redemption_fee_bps(&config, 0), 100);
+        assert_eq!(redemption_fee_bps(&config, 999), 100);
+        assert_eq!(redemption_fee_bps(&config, 1_000), 25);
+
+        // No threshold: everyone pays the base fee
+        let config = GlobalConfig { staker_fee_threshold_xls: 0, ..config };
+        assert_eq!(redemption_fee_bps(&config, u64::MAX), 100);
+    }
+}
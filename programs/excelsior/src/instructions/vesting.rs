@@ -0,0 +1,266 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked, Burn};
+use crate::state::*;
+use crate::pricing::{self, MintDecimals};
+use crate::transfer_fee::fee_adjusted;
+use crate::math::*;
+use crate::events::*;
+
+#[derive(Accounts)]
+pub struct BuyXlsLocked<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"vesting", user.key().as_ref()],
+        bump,
+        space = VestingEscrow::LEN
+    )]
+    pub vesting_escrow: Box<Account<'info, VestingEscrow>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"purchase_record", user.key().as_ref()],
+        bump,
+        space = PurchaseRecord::LEN
+    )]
+    pub purchase_record: Box<Account<'info, PurchaseRecord>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"vesting_vault", vesting_escrow.key().as_ref()],
+        bump,
+        token::mint = xls_mint,
+        token::authority = global_config,
+        token::token_program = token_program,
+    )] // Locked XLS for this escrow
+    pub vesting_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_lxr_account.mint == lxr_mint.key() @ crate::ErrorCode::UserLxrMintMismatch,
+        constraint = user_lxr_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_lxr_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault,
+        constraint = xls_vault_supply.mint == xls_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = xls_vault_supply.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault,
+        constraint = rwa_vault_lxr.mint == lxr_mint.key() @ crate::ErrorCode::VaultMintMismatch,
+        constraint = rwa_vault_lxr.owner == global_config.key() @ crate::ErrorCode::VaultOwnerMismatch,
+    )]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseVested<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        mut,
+        seeds = [b"vesting", user.key().as_ref()],
+        bump = vesting_escrow.bump,
+    )]
+    pub vesting_escrow: Box<Account<'info, VestingEscrow>>,
+
+    #[account(
+        mut,
+        seeds = [b"vesting_vault", vesting_escrow.key().as_ref()],
+        bump,
+    )]
+    pub vesting_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_xls_account.mint == xls_mint.key() @ crate::ErrorCode::UserXlsMintMismatch,
+        constraint = user_xls_account.owner == user.key() @ crate::ErrorCode::UserAccountOwnerMismatch,
+    )]
+    pub user_xls_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_2022::ID @ crate::ErrorCode::InvalidTokenProgram)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn buy_locked_handler(
+    ctx: Context<BuyXlsLocked>,
+    amount_xls: u64,
+    tier_index: u8,
+    max_lxr_in: u64,
+    expires_at: i64,
+) -> Result<()> {
+    require!(amount_xls > 0, crate::ErrorCode::ZeroAmount);
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= expires_at, crate::ErrorCode::QuoteExpired);
+    require!(ctx.accounts.xls_vault_supply.amount >= amount_xls, crate::ErrorCode::VaultInsufficient);
+
+    let tier = *ctx.accounts.global_config
+        .lock_tiers
+        .get(tier_index as usize)
+        .filter(|t| t.duration > 0)
+        .ok_or(crate::ErrorCode::InvalidLockTier)?;
+
+    let escrow = &ctx.accounts.vesting_escrow;
+    require!(escrow.released_xls == escrow.total_xls, crate::ErrorCode::VestingActive);
+
+    // Same price as buy_xls, less the tier's discount
+    let decimals = MintDecimals {
+        xls: ctx.accounts.xls_mint.decimals,
+        lxr: ctx.accounts.lxr_mint.decimals,
+    };
+    let list_price = pricing::quote_buy(&ctx.accounts.global_config, amount_xls, decimals)?;
+    let lxr_needed = list_price.safe_sub(bps_of(list_price, tier.discount_bps as u64)?)?;
+
+    let (burn_amount, vault_amount) = pricing::split_purchase(&ctx.accounts.global_config, lxr_needed)?;
+    let vault_transfer = fee_adjusted(
+        &ctx.accounts.lxr_mint,
+        vault_amount,
+        ctx.accounts.global_config.gross_up_transfer_fees,
+    )?;
+    let lxr_paid = burn_amount.safe_add(vault_transfer.send)?;
+    require!(lxr_paid <= max_lxr_in, crate::ErrorCode::MaxLxrInExceeded);
+
+    // 1. Transfer LXR to RWA Vault
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.user_lxr_account.to_account_info(),
+            mint: ctx.accounts.lxr_mint.to_account_info(),
+            to: ctx.accounts.rwa_vault_lxr.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        }
+    );
+    token_interface::transfer_checked(transfer_ctx, vault_transfer.send, ctx.accounts.lxr_mint.decimals)?;
+
+    // 2. Burn LXR
+    let burn_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Burn {
+            mint: ctx.accounts.lxr_mint.to_account_info(),
+            from: ctx.accounts.user_lxr_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        }
+    );
+    token_interface::burn(burn_ctx, burn_amount)?;
+
+    let config = &mut ctx.accounts.global_config;
+    let record = &mut ctx.accounts.purchase_record;
+    record.owner = ctx.accounts.user.key();
+    record.bump = ctx.bumps.purchase_record;
+    pricing::record_purchase(config, record, amount_xls, now)?;
+    config.total_lxr_burned = config.total_lxr_burned.safe_add(burn_amount)?;
+    config.total_xls_sold = config.total_xls_sold.safe_add(amount_xls)?;
+
+    // 3. Lock XLS in the user's Vesting Vault
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let transfer_xls = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.xls_vault_supply.to_account_info(),
+            mint: ctx.accounts.xls_mint.to_account_info(),
+            to: ctx.accounts.vesting_vault.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer_xls, amount_xls, ctx.accounts.xls_mint.decimals)?;
+
+    let escrow = &mut ctx.accounts.vesting_escrow;
+    escrow.owner = ctx.accounts.user.key();
+    escrow.total_xls = amount_xls;
+    escrow.released_xls = 0;
+    escrow.start_ts = now;
+    escrow.end_ts = now.safe_add(tier.duration)?;
+    escrow.cliff = tier.cliff;
+    escrow.bump = ctx.bumps.vesting_escrow;
+
+    emit!(XlsPurchasedLocked {
+        buyer: escrow.owner,
+        amount_xls,
+        lxr_paid,
+        discount_bps: tier.discount_bps,
+        start_ts: escrow.start_ts,
+        end_ts: escrow.end_ts,
+        cliff: escrow.cliff,
+    });
+
+    msg!(
+        "Locked Purchase: Paid {} LXR ({} bps discount), {} XLS vesting until {}",
+        lxr_paid, tier.discount_bps, amount_xls, escrow.end_ts
+    );
+    Ok(())
+}
+
+pub fn release_vested_handler(ctx: Context<ReleaseVested>) -> Result<()> {
+    let escrow = &ctx.accounts.vesting_escrow;
+    let releasable = escrow
+        .vested(Clock::get()?.unix_timestamp)?
+        .safe_sub(escrow.released_xls)?;
+    require!(releasable > 0, crate::ErrorCode::NothingVested);
+
+    let seeds = &[b"global_config".as_ref(), &[ctx.accounts.global_config.bump]];
+    let signer = &[&seeds[..]];
+
+    let transfer_xls = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.vesting_vault.to_account_info(),
+            mint: ctx.accounts.xls_mint.to_account_info(),
+            to: ctx.accounts.user_xls_account.to_account_info(),
+            authority: ctx.accounts.global_config.to_account_info(),
+        },
+        signer
+    );
+    token_interface::transfer_checked(transfer_xls, releasable, ctx.accounts.xls_mint.decimals)?;
+
+    let escrow = &mut ctx.accounts.vesting_escrow;
+    escrow.released_xls = escrow.released_xls.safe_add(releasable)?;
+
+    emit!(VestedReleased {
+        user: escrow.owner,
+        amount_xls: releasable,
+        released_xls: escrow.released_xls,
+        total_xls: escrow.total_xls,
+    });
+
+    msg!("Released {} XLS ({}/{})", releasable, escrow.released_xls, escrow.total_xls);
+    Ok(())
+}
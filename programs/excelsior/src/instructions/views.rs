@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use crate::state::*;
+use crate::pricing::{self, MintDecimals};
+use crate::math::*;
+use crate::instructions::swap::{self, BuyQuote, RedeemQuote};
+
+// Read-only instructions. Results are Borsh-encoded into the transaction's return data,
+// so clients simulate them instead of re-implementing the on-chain math.
+
+#[derive(Accounts)]
+pub struct QuoteView<'info> {
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(address = global_config.xls_mint @ crate::ErrorCode::InvalidXlsMint)]
+    pub xls_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = global_config.lxr_mint @ crate::ErrorCode::InvalidLxrMint)]
+    pub lxr_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = global_config.xls_vault_supply @ crate::ErrorCode::InvalidSupplyVault)]
+    pub xls_vault_supply: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = global_config.rwa_vault_lxr @ crate::ErrorCode::InvalidRwaVault)]
+    pub rwa_vault_lxr: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub user_account: Option<Box<Account<'info, UserAccount>>>, // Optional: quote_redeem applies this staker's fee tier
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct PendingRewardsView<'info> {
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        seeds = [b"user_account", user.as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Box<Account<'info, UserAccount>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct NavView {
+    pub circulating_xls: u64,
+    pub free_vault_lxr: u64, // RWA Vault less reserved LXR
+    pub nav_per_xls: u64, // Free Vault LXR per 1 whole XLS (0 = Nothing circulating)
+    pub redemption_per_xls: u64, // Instant redemption value of 1 whole XLS under the current mode, before the exit fee
+    pub coverage_bps: u64,
+}
+
+pub fn quote_buy_handler(ctx: Context<QuoteView>, amount_xls: u64) -> Result<BuyQuote> {
+    swap::buy_quote(&ctx.accounts.global_config, &ctx.accounts.xls_mint, &ctx.accounts.lxr_mint, amount_xls)
+}
+
+pub fn quote_redeem_handler(ctx: Context<QuoteView>, amount_xls: u64) -> Result<RedeemQuote> {
+    let config = &ctx.accounts.global_config;
+    let circulating = pricing::circulating_xls(config, ctx.accounts.xls_mint.supply, ctx.accounts.xls_vault_supply.amount)?;
+    swap::redeem_quote(
+        config,
+        &ctx.accounts.lxr_mint,
+        MintDecimals { xls: ctx.accounts.xls_mint.decimals, lxr: ctx.accounts.lxr_mint.decimals },
+        pricing::free_vault_lxr(config, ctx.accounts.rwa_vault_lxr.amount),
+        circulating,
+        ctx.accounts.user_account.as_ref().map_or(0, |account| account.staked_xls),
+        amount_xls,
+    )
+}
+
+/// Includes emission accrued since the last update, as the next stake/unstake would settle it.
+pub fn pending_rewards_handler(ctx: Context<PendingRewardsView>, _user: Pubkey) -> Result<u64> {
+    let mut config = GlobalConfig::clone(&ctx.accounts.global_config);
+    pricing::accrue_emissions(&mut config, Clock::get()?.unix_timestamp)?;
+    ctx.accounts.user_account.pending_rewards(config.acc_rewards_per_share)
+}
+
+pub fn get_nav_handler(ctx: Context<QuoteView>) -> Result<NavView> {
+    let config = &ctx.accounts.global_config;
+    let decimals = MintDecimals { xls: ctx.accounts.xls_mint.decimals, lxr: ctx.accounts.lxr_mint.decimals };
+    let circulating_xls = pricing::circulating_xls(config, ctx.accounts.xls_mint.supply, ctx.accounts.xls_vault_supply.amount)?;
+    let free_vault_lxr = pricing::free_vault_lxr(config, ctx.accounts.rwa_vault_lxr.amount);
+
+    let one_xls = 10u64
+        .checked_pow(decimals.xls as u32)
+        .ok_or(crate::ErrorCode::MathOverflow)?;
+    let nav_per_xls = if circulating_xls == 0 {
+        0
+    } else {
+        mul_div(free_vault_lxr, one_xls, circulating_xls)?
+    };
+    // NAV Mode cannot price a unit larger than what circulates
+    let redemption_per_xls = match config.redemption_mode {
+        RedemptionMode::Nav if circulating_xls < one_xls => 0,
+        _ => pricing::redemption_owed(config, one_xls, free_vault_lxr, circulating_xls, decimals)?,
+    };
+
+    Ok(NavView {
+        circulating_xls,
+        free_vault_lxr,
+        nav_per_xls,
+        redemption_per_xls,
+        coverage_bps: pricing::coverage_bps(config, free_vault_lxr, circulating_xls, decimals)?,
+    })
+}
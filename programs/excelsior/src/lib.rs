@@ -1,7 +1,15 @@
+// Anchor 0.29 macros emit cfgs (target_os = "solana", custom-heap, ...) newer rustc does not know
+#![allow(unexpected_cfgs)]
+
 use anchor_lang::prelude::*;
 
+pub mod ed25519;
+pub mod events;
 pub mod instructions;
+pub mod math;
+pub mod pricing;
 pub mod state;
+pub mod transfer_fee;
 
 use instructions::init_ix::*;
 use instructions::swap::*;
@@ -9,6 +17,14 @@ use instructions::stake::*;
 use instructions::fees::*;
 use instructions::rewards::*;
 use instructions::admin_ops::*;
+use instructions::breaker::*;
+use instructions::redeem_queue::*;
+use instructions::vesting::*;
+use instructions::referral::*;
+use instructions::otc::*;
+use instructions::auction::*;
+use instructions::pool::*;
+use instructions::views::*;
 
 declare_id!("CihitmkdTdh48gvUZSjU7rZ8EARQksJNxspwnRu7ZhAp"); // Force Rebuild 
 
@@ -20,18 +36,22 @@ pub mod excelsior {
         instructions::init_ix::handler(ctx, params)
     }
 
-    pub fn buy_xls(ctx: Context<BuyXls>, amount_xls: u64) -> Result<()> {
-        instructions::swap::buy_handler(ctx, amount_xls)
+    pub fn buy_xls(ctx: Context<BuyXls>, amount_xls: u64, max_lxr_in: u64, expires_at: i64) -> Result<()> {
+        instructions::swap::buy_handler(ctx, amount_xls, max_lxr_in, expires_at)
     }
 
-    pub fn redeem_xls(ctx: Context<RedeemXls>, amount_xls: u64) -> Result<()> {
-        instructions::swap::redeem_handler(ctx, amount_xls)
+    pub fn redeem_xls(ctx: Context<RedeemXls>, amount_xls: u64, min_lxr_out: u64, expires_at: i64) -> Result<()> {
+        instructions::swap::redeem_handler(ctx, amount_xls, min_lxr_out, expires_at)
     }
 
     pub fn init_user(ctx: Context<InitUser>) -> Result<()> {
         instructions::stake::init_user_handler(ctx)
     }
 
+    pub fn migrate_user_account(ctx: Context<MigrateUserAccount>, user: Pubkey) -> Result<()> {
+        instructions::stake::migrate_user_account_handler(ctx, user)
+    }
+
     pub fn stake_xls(ctx: Context<StakeXls>, amount: u64) -> Result<()> {
         instructions::stake::stake_handler(ctx, amount)
     }
@@ -40,6 +60,26 @@ pub mod excelsior {
         instructions::stake::unstake_handler(ctx, amount)
     }
 
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        instructions::stake::claim_rewards_handler(ctx)
+    }
+
+    pub fn lock_stake(ctx: Context<LockStake>, lock: StakeLock) -> Result<()> {
+        instructions::stake::lock_stake_handler(ctx, lock)
+    }
+
+    pub fn expire_lock(ctx: Context<ExpireLock>, user: Pubkey) -> Result<()> {
+        instructions::stake::expire_lock_handler(ctx, user)
+    }
+
+    pub fn withdraw_unbonded(ctx: Context<WithdrawUnbonded>) -> Result<()> {
+        instructions::stake::withdraw_unbonded_handler(ctx)
+    }
+
+    pub fn cancel_unbonding(ctx: Context<CancelUnbonding>) -> Result<()> {
+        instructions::stake::cancel_unbonding_handler(ctx)
+    }
+
     pub fn harvest_fees(ctx: Context<HarvestFees>) -> Result<()> {
         instructions::fees::harvest_handler(ctx)
     }
@@ -63,6 +103,175 @@ pub mod excelsior {
     pub fn upgrade_config(ctx: Context<UpgradeConfig>) -> Result<()> {
         instructions::admin_ops::upgrade_config_handler(ctx)
     }
+
+    pub fn set_redemption_params(ctx: Context<SetRedemptionParams>, params: RedemptionParams) -> Result<()> {
+        instructions::admin_ops::set_redemption_params_handler(ctx, params)
+    }
+
+    pub fn set_swap_params(ctx: Context<SetSwapParams>, params: SwapParams) -> Result<()> {
+        instructions::admin_ops::set_swap_params_handler(ctx, params)
+    }
+
+    pub fn set_sale_curve(ctx: Context<SetSaleCurve>, params: SaleCurveParams) -> Result<()> {
+        instructions::admin_ops::set_sale_curve_handler(ctx, params)
+    }
+
+    pub fn set_transfer_fee_mode(ctx: Context<SetTransferFeeMode>, gross_up: bool) -> Result<()> {
+        instructions::admin_ops::set_transfer_fee_mode_handler(ctx, gross_up)
+    }
+
+    pub fn set_redemption_breaker(ctx: Context<SetRedemptionBreaker>, params: RedemptionBreakerParams) -> Result<()> {
+        instructions::breaker::set_redemption_breaker_handler(ctx, params)
+    }
+
+    pub fn poke_redemption_breaker(ctx: Context<PokeRedemptionBreaker>) -> Result<()> {
+        instructions::breaker::poke_redemption_breaker_handler(ctx)
+    }
+
+    pub fn set_emission_schedule(ctx: Context<SetEmissionSchedule>, params: EmissionParams) -> Result<()> {
+        instructions::admin_ops::set_emission_schedule_handler(ctx, params)
+    }
+
+    pub fn sweep_undistributed_rewards(ctx: Context<SweepUndistributedRewards>, amount: u64) -> Result<()> {
+        instructions::admin_ops::sweep_undistributed_rewards_handler(ctx, amount)
+    }
+
+    pub fn set_unbonding_period(ctx: Context<SetUnbondingPeriod>, unbonding_period: i64) -> Result<()> {
+        instructions::admin_ops::set_unbonding_period_handler(ctx, unbonding_period)
+    }
+
+    pub fn set_redemption_fee(ctx: Context<SetRedemptionFee>, params: RedemptionFeeParams) -> Result<()> {
+        instructions::admin_ops::set_redemption_fee_handler(ctx, params)
+    }
+
+    pub fn set_redeem_queue_params(ctx: Context<SetRedeemQueueParams>, epoch_duration: i64) -> Result<()> {
+        instructions::admin_ops::set_redeem_queue_params_handler(ctx, epoch_duration)
+    }
+
+    pub fn set_payment_mint(ctx: Context<SetPaymentMint>, params: PaymentMintParams) -> Result<()> {
+        instructions::admin_ops::set_payment_mint_handler(ctx, params)
+    }
+
+    pub fn buy_xls_with(ctx: Context<BuyXlsWith>, amount_xls: u64, max_payment_in: u64, expires_at: i64) -> Result<()> {
+        instructions::swap::buy_with_handler(ctx, amount_xls, max_payment_in, expires_at)
+    }
+
+    pub fn set_purchase_caps(ctx: Context<SetPurchaseCaps>, params: PurchaseCapParams) -> Result<()> {
+        instructions::admin_ops::set_purchase_caps_handler(ctx, params)
+    }
+
+    pub fn set_referral_params(ctx: Context<SetReferralParams>, referral_bps: u16) -> Result<()> {
+        instructions::admin_ops::set_referral_params_handler(ctx, referral_bps)
+    }
+
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        instructions::referral::register_referrer_handler(ctx)
+    }
+
+    pub fn claim_referral(ctx: Context<ClaimReferral>) -> Result<()> {
+        instructions::referral::claim_referral_handler(ctx)
+    }
+
+    pub fn set_quote_signer(ctx: Context<SetQuoteSigner>, quote_signer: Pubkey) -> Result<()> {
+        instructions::admin_ops::set_quote_signer_handler(ctx, quote_signer)
+    }
+
+    pub fn buy_xls_otc(
+        ctx: Context<BuyXlsOtc>,
+        amount_xls: u64,
+        price: u64,
+        nonce: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        instructions::otc::buy_otc_handler(ctx, amount_xls, price, nonce, expires_at)
+    }
+
+    pub fn set_lock_tiers(ctx: Context<SetLockTiers>, params: LockTierParams) -> Result<()> {
+        instructions::admin_ops::set_lock_tiers_handler(ctx, params)
+    }
+
+    pub fn buy_xls_locked(
+        ctx: Context<BuyXlsLocked>,
+        amount_xls: u64,
+        tier_index: u8,
+        max_lxr_in: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        instructions::vesting::buy_locked_handler(ctx, amount_xls, tier_index, max_lxr_in, expires_at)
+    }
+
+    pub fn release_vested(ctx: Context<ReleaseVested>) -> Result<()> {
+        instructions::vesting::release_vested_handler(ctx)
+    }
+
+    pub fn request_redeem(ctx: Context<RequestRedeem>, amount_xls: u64) -> Result<()> {
+        instructions::redeem_queue::request_redeem_handler(ctx, amount_xls)
+    }
+
+    pub fn cancel_redeem(ctx: Context<CancelRedeem>) -> Result<()> {
+        instructions::redeem_queue::cancel_redeem_handler(ctx)
+    }
+
+    pub fn settle_redeem_epoch(ctx: Context<SettleRedeemEpoch>) -> Result<()> {
+        instructions::redeem_queue::settle_redeem_epoch_handler(ctx)
+    }
+
+    pub fn claim_redeem(ctx: Context<ClaimRedeem>) -> Result<()> {
+        instructions::redeem_queue::claim_redeem_handler(ctx)
+    }
+
+    pub fn create_auction(ctx: Context<CreateAuction>, params: AuctionParams) -> Result<()> {
+        instructions::auction::create_auction_handler(ctx, params)
+    }
+
+    pub fn buy_xls_auction(ctx: Context<BuyXlsAuction>, amount_xls: u64, max_lxr_in: u64, expires_at: i64) -> Result<()> {
+        instructions::auction::buy_auction_handler(ctx, amount_xls, max_lxr_in, expires_at)
+    }
+
+    pub fn close_auction(ctx: Context<CloseAuction>) -> Result<()> {
+        instructions::auction::close_auction_handler(ctx)
+    }
+
+    pub fn init_pool(ctx: Context<InitPool>, params: PoolFeeParams) -> Result<()> {
+        instructions::pool::init_pool_handler(ctx, params)
+    }
+
+    pub fn set_pool_fees(ctx: Context<SetPoolFees>, params: PoolFeeParams) -> Result<()> {
+        instructions::pool::set_pool_fees_handler(ctx, params)
+    }
+
+    pub fn add_liquidity(ctx: Context<ModifyLiquidity>, max_lxr_in: u64, max_xls_in: u64, min_lp_out: u64) -> Result<()> {
+        instructions::pool::add_liquidity_handler(ctx, max_lxr_in, max_xls_in, min_lp_out)
+    }
+
+    pub fn remove_liquidity(ctx: Context<ModifyLiquidity>, lp_amount: u64, min_lxr_out: u64, min_xls_out: u64) -> Result<()> {
+        instructions::pool::remove_liquidity_handler(ctx, lp_amount, min_lxr_out, min_xls_out)
+    }
+
+    pub fn swap_pool(
+        ctx: Context<SwapPool>,
+        amount_in: u64,
+        min_amount_out: u64,
+        direction: SwapDirection,
+    ) -> Result<()> {
+        instructions::pool::swap_pool_handler(ctx, amount_in, min_amount_out, direction)
+    }
+
+    pub fn quote_buy(ctx: Context<QuoteView>, amount_xls: u64) -> Result<BuyQuote> {
+        instructions::views::quote_buy_handler(ctx, amount_xls)
+    }
+
+    pub fn quote_redeem(ctx: Context<QuoteView>, amount_xls: u64) -> Result<RedeemQuote> {
+        instructions::views::quote_redeem_handler(ctx, amount_xls)
+    }
+
+    pub fn pending_rewards(ctx: Context<PendingRewardsView>, user: Pubkey) -> Result<u64> {
+        instructions::views::pending_rewards_handler(ctx, user)
+    }
+
+    pub fn get_nav(ctx: Context<QuoteView>) -> Result<NavView> {
+        instructions::views::get_nav_handler(ctx)
+    }
 }
 
 #[error_code]
@@ -73,4 +282,150 @@ pub enum ErrorCode {
     InvalidProof,
     #[msg("Inflation trigger not yet ready.")]
     InflationNotReady,
+    #[msg("Invalid redemption parameters.")]
+    InvalidRedemptionParams,
+    #[msg("Quote expired.")]
+    QuoteExpired,
+    #[msg("LXR required exceeds max_lxr_in.")]
+    MaxLxrInExceeded,
+    #[msg("LXR returned is below min_lxr_out.")]
+    MinLxrOutNotMet,
+    #[msg("Invalid swap parameters.")]
+    InvalidSwapParams,
+    #[msg("Burn share exceeds the hard cap.")]
+    BurnBpsAboveCap,
+    #[msg("Invalid sale curve tiers.")]
+    InvalidSaleCurve,
+    #[msg("XLS mint does not match config.")]
+    InvalidXlsMint,
+    #[msg("LXR mint does not match config.")]
+    InvalidLxrMint,
+    #[msg("XLS supply vault does not match config.")]
+    InvalidSupplyVault,
+    #[msg("RWA vault does not match config.")]
+    InvalidRwaVault,
+    #[msg("XLS staking vault does not match config.")]
+    InvalidStakingVault,
+    #[msg("LXR reward vault does not match config.")]
+    InvalidRewardVault,
+    #[msg("Vault holds the wrong mint.")]
+    VaultMintMismatch,
+    #[msg("Vault is not owned by the config PDA.")]
+    VaultOwnerMismatch,
+    #[msg("User XLS account holds the wrong mint.")]
+    UserXlsMintMismatch,
+    #[msg("User LXR account holds the wrong mint.")]
+    UserLxrMintMismatch,
+    #[msg("User token account is not owned by the signer.")]
+    UserAccountOwnerMismatch,
+    #[msg("Token program must be Token-2022.")]
+    InvalidTokenProgram,
+    #[msg("Math overflow.")]
+    MathOverflow,
+    #[msg("Amount must be greater than zero.")]
+    ZeroAmount,
+    #[msg("Vault balance is insufficient.")]
+    VaultInsufficient,
+    #[msg("Redemptions are halted by the circuit breaker.")]
+    RedemptionsHalted,
+    #[msg("Redemption would drop reserve coverage below the threshold.")]
+    CoverageBelowThreshold,
+    #[msg("Circuit breaker is pinned by the admin.")]
+    BreakerOverridden,
+    #[msg("Redemption epoch duration must be positive.")]
+    InvalidRedeemQueueParams,
+    #[msg("Redemption epoch is still open.")]
+    RedeemEpochNotReady,
+    #[msg("Redemption epoch is already settled.")]
+    RedeemEpochSettled,
+    #[msg("Redemption epoch is not settled yet.")]
+    RedeemEpochNotSettled,
+    #[msg("Payment mint price must be positive.")]
+    InvalidPaymentParams,
+    #[msg("XLS cannot be registered as a payment mint.")]
+    InvalidPaymentMint,
+    #[msg("Payment mint is not accepted.")]
+    PaymentMintDisabled,
+    #[msg("Payment vault does not match the registry.")]
+    InvalidPaymentVault,
+    #[msg("User payment account mint mismatch.")]
+    UserPaymentMintMismatch,
+    #[msg("Slippage: payment required exceeds max_payment_in.")]
+    MaxPaymentInExceeded,
+    #[msg("Lock tiers must be contiguous, increasing and within the discount cap.")]
+    InvalidLockTiers,
+    #[msg("Lock tier is not active.")]
+    InvalidLockTier,
+    #[msg("Previous locked purchase is still vesting.")]
+    VestingActive,
+    #[msg("Nothing has vested yet.")]
+    NothingVested,
+    #[msg("Purchase exceeds the per-wallet cap.")]
+    WalletCapExceeded,
+    #[msg("Purchase exceeds the sale epoch cap.")]
+    EpochCapExceeded,
+    #[msg("Epoch cap requires a positive epoch duration.")]
+    InvalidPurchaseCaps,
+    #[msg("Referral share above cap.")]
+    InvalidReferralParams,
+    #[msg("Buyers cannot refer themselves.")]
+    SelfReferral,
+    #[msg("Referrer was referred by this buyer.")]
+    CircularReferral,
+    #[msg("Buyer is already bound to a different referrer.")]
+    ReferrerMismatch,
+    #[msg("Referrer purchase record missing or invalid.")]
+    InvalidReferrerRecord,
+    #[msg("No referral rewards to claim.")]
+    NoReferralRewards,
+    #[msg("OTC quotes are disabled (no quote signer).")]
+    OtcDisabled,
+    #[msg("Missing Ed25519 verification instruction.")]
+    MissingEd25519Instruction,
+    #[msg("Ed25519 instruction does not match the quote or signer.")]
+    InvalidOtcSignature,
+    #[msg("Invalid auction parameters.")]
+    InvalidAuctionParams,
+    #[msg("Auction is not running.")]
+    AuctionNotActive,
+    #[msg("Invalid pool fee parameters.")]
+    InvalidPoolParams,
+    #[msg("Pool has no liquidity.")]
+    PoolEmpty,
+    #[msg("User LP account holds the wrong mint.")]
+    UserLpMintMismatch,
+    #[msg("Slippage: LP minted is below min_lp_out.")]
+    MinLpOutNotMet,
+    #[msg("Slippage: output is below the minimum.")]
+    MinAmountOutNotMet,
+    #[msg("Redemption fee above cap or staker tier above the base fee.")]
+    InvalidRedemptionFee,
+    #[msg("No staking rewards to claim.")]
+    NoStakingRewards,
+    #[msg("Stake is locked until lock_end.")]
+    StakeLocked,
+    #[msg("A stake lock can be extended but not shortened.")]
+    LockShortened,
+    #[msg("No unstaked XLS is unbonding.")]
+    NothingUnbonding,
+    #[msg("Unbonding period has not elapsed yet.")]
+    StillUnbonding,
+    #[msg("Unbonding period must be between 0 and 30 days.")]
+    InvalidUnbondingPeriod,
+    #[msg("User account already has the current layout.")]
+    UserAccountCurrent,
+    #[msg("Staking vault must differ from the supply vault.")]
+    SharedStakingVault,
+    #[msg("xls_mint and token_program are required to move staked XLS out of a shared vault.")]
+    StakeMigrationAccountsMissing,
+    #[msg("Stake lock is still active or carries no boost.")]
+    LockNotExpired,
+    #[msg("Buyer referrer account missing or invalid.")]
+    InvalidBuyerReferrer,
+    #[msg("Wallets that have already referred buyers cannot be referred.")]
+    BuyerHasReferrals,
+    #[msg("Withdraw or cancel the unbonding XLS before unstaking more.")]
+    AlreadyUnbonding,
+    #[msg("Staking vault cannot be replaced while it holds staked or unbonding XLS.")]
+    StakingVaultInUse,
 }
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use crate::ErrorCode;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Precision of acc_rewards_per_share (1e12).
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Checked arithmetic that fails with `ErrorCode::MathOverflow` instead of panicking.
+pub trait SafeMath: Sized {
+    fn safe_add(self, rhs: Self) -> Result<Self>;
+    fn safe_sub(self, rhs: Self) -> Result<Self>;
+    fn safe_mul(self, rhs: Self) -> Result<Self>;
+    fn safe_div(self, rhs: Self) -> Result<Self>;
+}
+
+macro_rules! impl_safe_math {
+    ($($t:ty),*) => {
+        $(
+            impl SafeMath for $t {
+                fn safe_add(self, rhs: Self) -> Result<Self> {
+                    self.checked_add(rhs).ok_or_else(|| error!(ErrorCode::MathOverflow))
+                }
+                fn safe_sub(self, rhs: Self) -> Result<Self> {
+                    self.checked_sub(rhs).ok_or_else(|| error!(ErrorCode::MathOverflow))
+                }
+                fn safe_mul(self, rhs: Self) -> Result<Self> {
+                    self.checked_mul(rhs).ok_or_else(|| error!(ErrorCode::MathOverflow))
+                }
+                fn safe_div(self, rhs: Self) -> Result<Self> {
+                    self.checked_div(rhs).ok_or_else(|| error!(ErrorCode::MathOverflow))
+                }
+            }
+        )*
+    };
+}
+
+impl_safe_math!(u64, u128, i64);
+
+/// Narrows a u128 intermediate back to a token amount.
+pub fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// `value * numerator / denominator` through a u128 intermediate (rounds down).
+pub fn mul_div(value: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    to_u64((value as u128).safe_mul(numerator as u128)?.safe_div(denominator as u128)?)
+}
+
+/// Share of `amount` in basis points (rounds down).
+pub fn bps_of(amount: u64, bps: u64) -> Result<u64> {
+    mul_div(amount, bps, BPS_DENOMINATOR)
+}
+
+/// Integer square root (floor), used to size the first LP deposit.
+pub fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
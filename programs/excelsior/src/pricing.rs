@@ -0,0 +1,478 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::math::*;
+
+/// Default Sale Price: 1 XLS = 1,000,000 LXR, split 30% Burn / 70% RWA Vault.
+pub const DEFAULT_SWAP_PRICE: u64 = 1_000_000;
+pub const DEFAULT_SWAP_BURN_BPS: u16 = 3_000;
+pub const DEFAULT_SWAP_VAULT_BPS: u16 = 7_000;
+/// Hard Cap on the burned share of a purchase (50%).
+pub const MAX_SWAP_BURN_BPS: u16 = 5_000;
+/// Hard Cap on the discount for locked purchases (50%).
+pub const MAX_LOCK_DISCOUNT_BPS: u16 = 5_000;
+/// Hard Cap on the referrer's share of the RWA Vault leg (20%).
+pub const MAX_REFERRAL_BPS: u16 = 2_000;
+
+/// Default Book Value: 1 XLS redeems for 700,000 LXR (the 70% that entered the RWA Vault).
+pub const DEFAULT_REDEMPTION_RATE: u64 = 700_000;
+
+/// Default Redemption Queue epoch: 1 Day.
+pub const DEFAULT_REDEEM_EPOCH_DURATION: i64 = 86_400;
+
+/// Hard Cap on the redemption exit fee (10%).
+pub const MAX_REDEMPTION_FEE_BPS: u16 = 1_000;
+
+/// Hard Cap on the staking unbonding period: 30 Days.
+pub const MAX_UNBONDING_PERIOD: i64 = 30 * 86_400;
+
+/// Hard Cap on the LXR/XLS pool's swap fee (10%).
+pub const MAX_POOL_FEE_BPS: u16 = 1_000;
+
+/// Decimals of the XLS and LXR mints, read from the mint accounts on every call.
+/// All prices are UI-unit ratios (LXR per 1 XLS), so amounts are rescaled by 10^(lxr - xls).
+#[derive(Clone, Copy)]
+pub struct MintDecimals {
+    pub xls: u8,
+    pub lxr: u8,
+}
+
+impl MintDecimals {
+    /// Converts `amount_xls (atomic) * price (LXR per XLS)` into LXR atomic units.
+    /// Buys round up and redemptions round down, so precision loss never favours the caller.
+    pub fn to_lxr_units(&self, xls_times_price: u128, round_up: bool) -> Result<u128> {
+        to_price_units(xls_times_price, self.xls, self.lxr, round_up)
+    }
+}
+
+/// Converts `amount_xls (atomic) * price` into atomic units of the mint the price is quoted in,
+/// rescaling by 10^(price_decimals - xls_decimals). Used directly for non-LXR payment mints.
+pub fn to_price_units(xls_times_price: u128, xls_decimals: u8, price_decimals: u8, round_up: bool) -> Result<u128> {
+    if price_decimals >= xls_decimals {
+        let scale = 10u128
+            .checked_pow((price_decimals - xls_decimals) as u32)
+            .ok_or(crate::ErrorCode::MathOverflow)?;
+        xls_times_price.safe_mul(scale)
+    } else {
+        let scale = 10u128
+            .checked_pow((xls_decimals - price_decimals) as u32)
+            .ok_or(crate::ErrorCode::MathOverflow)?;
+        if round_up {
+            return Ok(xls_times_price.div_ceil(scale));
+        }
+        Ok(xls_times_price / scale)
+    }
+}
+
+/// LXR the buyer pays for the next `amount_xls` sold, under the configured Sale Mode.
+/// Shared by `buy_xls` and off-chain quoting so both use identical math.
+pub fn quote_buy(config: &GlobalConfig, amount_xls: u64, decimals: MintDecimals) -> Result<u64> {
+    let xls_times_price = match config.sale_mode {
+        SaleMode::Fixed => (amount_xls as u128).safe_mul(config.swap_price as u128)?,
+        SaleMode::Tiered => tiered_cost(&config.sale_tiers, config.total_xls_sold, amount_xls)?,
+    };
+
+    to_u64(decimals.to_lxr_units(xls_times_price, true)?)
+}
+
+/// Payment-mint units the buyer pays for `amount_xls` at a fixed price (payment mint per XLS).
+/// The payment mint is LXR for OTC quotes and auctions, or a registered mint for `buy_xls_with`.
+pub fn quote_buy_with(price: u64, amount_xls: u64, xls_decimals: u8, payment_decimals: u8) -> Result<u64> {
+    let xls_times_price = (amount_xls as u128).safe_mul(price as u128)?;
+    to_u64(to_price_units(xls_times_price, xls_decimals, payment_decimals, true)?)
+}
+
+/// Integrates the tier schedule exactly from `sold` to `sold + amount_xls`.
+/// A purchase crossing a boundary pays each tier's price for the portion inside it.
+/// Returns `XLS (atomic) * LXR per XLS`, to be rescaled by `MintDecimals::to_lxr_units`.
+pub fn tiered_cost(tiers: &[SaleTier], sold: u64, amount_xls: u64) -> Result<u128> {
+    let active = tiers.iter().take_while(|t| t.price > 0).count();
+    require!(active > 0, crate::ErrorCode::InvalidSaleCurve);
+
+    let mut cursor = sold;
+    let mut remaining = amount_xls;
+    let mut cost: u128 = 0;
+
+    for (i, tier) in tiers[..active].iter().enumerate() {
+        if remaining == 0 {
+            break;
+        }
+        let is_last = i == active - 1;
+        if !is_last && cursor >= tier.up_to {
+            continue;
+        }
+
+        let fill = if is_last { remaining } else { remaining.min(tier.up_to - cursor) };
+        cost = (fill as u128).safe_mul(tier.price as u128)?.safe_add(cost)?;
+        cursor = cursor.safe_add(fill)?;
+        remaining = remaining.safe_sub(fill)?;
+    }
+
+    Ok(cost)
+}
+
+/// Active tiers must be contiguous from index 0, end at increasing boundaries and never drop in price.
+pub fn validate_sale_tiers(tiers: &[SaleTier]) -> bool {
+    let active = tiers.iter().take_while(|t| t.price > 0).count();
+    if active == 0 || tiers[active..].iter().any(|t| t.price > 0) {
+        return false;
+    }
+    // The last active tier is open-ended, so only the ones before it need ordered boundaries
+    let bounded = &tiers[..active - 1];
+    !matches!(bounded.first(), Some(t) if t.up_to == 0)
+        && bounded.windows(2).all(|w| w[1].up_to > w[0].up_to)
+        && tiers[..active].windows(2).all(|w| w[1].price >= w[0].price)
+}
+
+/// Active lock tiers must be contiguous from index 0, lock longer at each step and never discount less.
+pub fn validate_lock_tiers(tiers: &[LockTier]) -> bool {
+    let active = tiers.iter().take_while(|t| t.duration > 0).count();
+    tiers[active..].iter().all(|t| t.duration == 0)
+        && tiers[..active].iter().all(|t| t.discount_bps <= MAX_LOCK_DISCOUNT_BPS)
+        && tiers[..active]
+            .windows(2)
+            .all(|w| w[1].duration > w[0].duration && w[1].discount_bps >= w[0].discount_bps)
+}
+
+/// Records a primary-sale purchase against the wallet and epoch caps, rolling the epoch window if it has ended.
+/// Shared by every path that sells XLS out of the supply vault.
+pub fn record_purchase(
+    config: &mut GlobalConfig,
+    record: &mut PurchaseRecord,
+    amount_xls: u64,
+    now: i64,
+) -> Result<()> {
+    let wallet_total = record.total_purchased.safe_add(amount_xls)?;
+    require!(
+        config.wallet_cap_xls == 0 || wallet_total <= config.wallet_cap_xls,
+        crate::ErrorCode::WalletCapExceeded
+    );
+
+    if config.sale_epoch_duration > 0 {
+        let elapsed = now.safe_sub(config.sale_epoch_start)?;
+        if elapsed >= config.sale_epoch_duration {
+            // Windows stay aligned to the original start
+            config.sale_epoch_start = now.safe_sub(elapsed % config.sale_epoch_duration)?;
+            config.sale_epoch_sold = 0;
+        }
+    }
+    let epoch_total = config.sale_epoch_sold.safe_add(amount_xls)?;
+    require!(
+        config.epoch_cap_xls == 0 || epoch_total <= config.epoch_cap_xls,
+        crate::ErrorCode::EpochCapExceeded
+    );
+
+    record.total_purchased = wallet_total;
+    config.sale_epoch_sold = epoch_total;
+    Ok(())
+}
+
+/// Splits a purchase into (Burn, RWA Vault) shares. The vault takes the remainder so nothing is lost to rounding.
+pub fn split_purchase(config: &GlobalConfig, lxr_amount: u64) -> Result<(u64, u64)> {
+    let burn_amount = bps_of(lxr_amount, config.swap_burn_bps as u64)?;
+    let vault_amount = lxr_amount.safe_sub(burn_amount)?;
+    Ok((burn_amount, vault_amount))
+}
+
+/// XLS held by the public: Mint Supply - Unsold Supply Vault - Open Auction Tranches - Staked and Unbonding XLS.
+/// When staking shares the supply vault, its balance already includes the staked and unbonding XLS.
+pub fn circulating_xls(config: &GlobalConfig, xls_supply: u64, xls_vault_supply_balance: u64) -> Result<u64> {
+    let staked_outside_supply_vault = if config.xls_vault_staking == config.xls_vault_supply {
+        0
+    } else {
+        config.total_staked_xls.safe_add(config.total_unbonding_xls)?
+    };
+    xls_supply
+        .safe_sub(xls_vault_supply_balance)?
+        .safe_sub(config.auction_xls_escrowed)?
+        .safe_sub(staked_outside_supply_vault)
+}
+
+/// Constant-product output for `amount_in` (already net of fees): out = R_out * in / (R_in + in), rounded down.
+pub fn pool_swap_out(reserve_in: u64, reserve_out: u64, amount_in: u64) -> Result<u64> {
+    require!(reserve_in > 0 && reserve_out > 0, crate::ErrorCode::PoolEmpty);
+    let out = (reserve_out as u128)
+        .safe_mul(amount_in as u128)?
+        .safe_div((reserve_in as u128).safe_add(amount_in as u128)?)?;
+    to_u64(out)
+}
+
+/// LP minted for a deposit. The first deposit mints sqrt(lxr * xls); later ones mint pro-rata
+/// to the scarcer side, so a deposit off the pool ratio only earns for its balanced part.
+pub fn lp_for_deposit(pool: &Pool, lxr_in: u64, xls_in: u64, lp_supply: u64) -> Result<u64> {
+    if lp_supply == 0 {
+        return to_u64(isqrt((lxr_in as u128).safe_mul(xls_in as u128)?));
+    }
+    require!(pool.lxr_reserve > 0 && pool.xls_reserve > 0, crate::ErrorCode::PoolEmpty);
+    Ok(mul_div(lxr_in, lp_supply, pool.lxr_reserve)?.min(mul_div(xls_in, lp_supply, pool.xls_reserve)?))
+}
+
+/// A reserve's share for `lp_amount` of LP. Deposits round up and withdrawals round down.
+pub fn pool_share(reserve: u64, lp_amount: u64, lp_supply: u64, round_up: bool) -> Result<u64> {
+    require!(lp_supply > 0, crate::ErrorCode::PoolEmpty);
+    let numerator = (reserve as u128).safe_mul(lp_amount as u128)?;
+    let share = if round_up {
+        numerator.div_ceil(lp_supply as u128)
+    } else {
+        numerator.safe_div(lp_supply as u128)?
+    };
+    to_u64(share)
+}
+
+/// Current Dutch auction price (LXR per XLS), interpolated linearly between start and end price.
+/// The decline rounds down so the price never undercuts the schedule.
+pub fn auction_price(auction: &Auction, now: i64) -> Result<u64> {
+    require!(
+        now >= auction.start_time && now <= auction.start_time.safe_add(auction.duration)?,
+        crate::ErrorCode::AuctionNotActive
+    );
+    let elapsed = now.safe_sub(auction.start_time)? as u128;
+    let decline = (auction.start_price.safe_sub(auction.end_price)? as u128)
+        .safe_mul(elapsed)?
+        .safe_div(auction.duration as u128)?;
+    to_u64((auction.start_price as u128).safe_sub(decline)?)
+}
+
+/// Lazily folds the per-second emission since `last_update_ts` into `acc_rewards_per_share`.
+/// Must run before anything that changes `total_effective_stake` or reads a staker's pending rewards.
+/// Emission stops at `emission_end_ts` or when the budget is spent; while nobody is staked the budget is kept.
+pub fn accrue_emissions(config: &mut GlobalConfig, now: i64) -> Result<()> {
+    let from = config.last_update_ts;
+    if now <= from {
+        return Ok(());
+    }
+    config.last_update_ts = now;
+
+    let until = now.min(config.emission_end_ts);
+    if until <= from || config.reward_rate_per_second == 0 || config.total_effective_stake == 0 {
+        return Ok(());
+    }
+    let emitted = to_u64(
+        (until.safe_sub(from)? as u128).safe_mul(config.reward_rate_per_second as u128)?,
+    )
+    .unwrap_or(u64::MAX)
+    .min(config.emission_budget);
+
+    config.emission_budget = config.emission_budget.safe_sub(emitted)?;
+    config.acc_rewards_per_share = config.acc_rewards_per_share.safe_add(
+        (emitted as u128)
+            .safe_mul(REWARD_PRECISION)?
+            .safe_div(config.total_effective_stake as u128)?,
+    )?;
+    Ok(())
+}
+
+/// Credits LXR that just landed in the Reward Vault to stakers via `acc_rewards_per_share`.
+/// While nobody is staked it is parked in `undistributed_rewards`; the next credit with stakers present
+/// (stake_xls credits 0 after each stake) folds the parked balance in alongside.
+pub fn credit_stakers(config: &mut GlobalConfig, amount: u64) -> Result<()> {
+    if config.total_effective_stake == 0 {
+        config.undistributed_rewards = config.undistributed_rewards.safe_add(amount)?;
+        return Ok(());
+    }
+    let total = amount.safe_add(config.undistributed_rewards)?;
+    config.undistributed_rewards = 0;
+    config.acc_rewards_per_share = config.acc_rewards_per_share.safe_add(
+        (total as u128)
+            .safe_mul(REWARD_PRECISION)?
+            .safe_div(config.total_effective_stake as u128)?,
+    )?;
+    Ok(())
+}
+
+/// Recomputes a staker's boosted weight after their stake or lock changes and re-bases reward_debt.
+/// Callers settle pending rewards first. An expired lock falls back to 1x here, at the staker's next interaction.
+pub fn rebase_stake(config: &mut GlobalConfig, user: &mut UserAccount, now: i64) -> Result<()> {
+    if now >= user.lock_end {
+        user.lock_boost_bps = BPS_DENOMINATOR as u16;
+    }
+    let effective = mul_div(user.staked_xls, user.lock_boost_bps as u64, BPS_DENOMINATOR)?;
+    config.total_effective_stake = config.total_effective_stake
+        .safe_sub(user.effective_stake)?
+        .safe_add(effective)?;
+    user.effective_stake = effective;
+    user.reward_debt = (effective as u128)
+        .safe_mul(config.acc_rewards_per_share)?
+        .safe_div(REWARD_PRECISION)?;
+    Ok(())
+}
+
+/// Exit fee for an instant redemption. Redeemers staking at least the threshold pay the reduced tier.
+pub fn redemption_fee_bps(config: &GlobalConfig, staked_xls: u64) -> u16 {
+    if config.staker_fee_threshold_xls > 0 && staked_xls >= config.staker_fee_threshold_xls {
+        config.staker_redemption_fee_bps
+    } else {
+        config.redemption_fee_bps
+    }
+}
+
+/// RWA Vault LXR not yet owed to settled redemption tickets or unclaimed referral rewards.
+pub fn free_vault_lxr(config: &GlobalConfig, vault_lxr: u64) -> u64 {
+    vault_lxr
+        .saturating_sub(config.redeem_lxr_reserved)
+        .saturating_sub(config.referral_lxr_reserved)
+}
+
+/// Reserve coverage in bps: RWA Vault LXR / (Circulating XLS * Book Value).
+/// With nothing circulating nothing is owed, reported as full coverage.
+pub fn coverage_bps(config: &GlobalConfig, vault_lxr: u64, circulating: u64, decimals: MintDecimals) -> Result<u64> {
+    let liability = decimals.to_lxr_units(
+        (circulating as u128).safe_mul(config.redemption_rate as u128)?,
+        true,
+    )?;
+    if liability == 0 {
+        return Ok(u64::MAX);
+    }
+    let coverage = (vault_lxr as u128)
+        .safe_mul(BPS_DENOMINATOR as u128)?
+        .safe_div(liability)?;
+    Ok(u64::try_from(coverage).unwrap_or(u64::MAX))
+}
+
+/// Circuit Breaker: after a redemption the vault must still cover the remaining circulating XLS at Book Value.
+/// Applies to instant redemptions and queue settlement alike, unless disabled or overridden by the admin.
+pub fn require_coverage_after(
+    config: &GlobalConfig,
+    vault_lxr_after: u64,
+    circulating_after: u64,
+    decimals: MintDecimals,
+) -> Result<()> {
+    if config.min_coverage_bps > 0 && !config.breaker_overridden {
+        let coverage_after = coverage_bps(config, vault_lxr_after, circulating_after, decimals)?;
+        require!(
+            coverage_after >= config.min_coverage_bps as u64,
+            crate::ErrorCode::CoverageBelowThreshold
+        );
+    }
+    Ok(())
+}
+
+/// LXR owed for `amount_xls` under the configured Redemption Mode, whether or not the vault can pay it.
+/// Must be evaluated BEFORE the redeemed XLS is burned (it is part of circulating supply).
+pub fn redemption_owed(
+    config: &GlobalConfig,
+    amount_xls: u64,
+    vault_lxr: u64,
+    circulating: u64,
+    decimals: MintDecimals,
+) -> Result<u64> {
+    let at_rate = |rate: u64| -> Result<u128> {
+        let xls_times_price = (amount_xls as u128).safe_mul(rate as u128)?;
+        decimals.to_lxr_units(xls_times_price, false)
+    };
+
+    let lxr_out = match config.redemption_mode {
+        RedemptionMode::Fixed => at_rate(config.redemption_rate)?,
+        RedemptionMode::Nav => {
+            require!(circulating > 0 && amount_xls <= circulating, crate::ErrorCode::InsufficientFunds);
+
+            // Share = (Amount XLS / Circulating XLS) * RWA Vault Balance (decimals cancel out)
+            let nav_value = (amount_xls as u128)
+                .safe_mul(vault_lxr as u128)?
+                .safe_div(circulating as u128)?;
+
+            let mut value = nav_value.max(at_rate(config.redemption_floor)?);
+            if config.redemption_ceiling > 0 {
+                value = value.min(at_rate(config.redemption_ceiling)?);
+            }
+            value
+        }
+    };
+
+    to_u64(lxr_out)
+}
+
+/// LXR paid out for an immediate redemption of `amount_xls`; fails if the vault cannot cover it.
+pub fn redemption_value(
+    config: &GlobalConfig,
+    amount_xls: u64,
+    vault_lxr: u64,
+    circulating: u64,
+    decimals: MintDecimals,
+) -> Result<u64> {
+    let lxr_out = redemption_owed(config, amount_xls, vault_lxr, circulating, decimals)?;
+    require!(lxr_out <= vault_lxr, crate::ErrorCode::VaultInsufficient);
+    Ok(lxr_out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XLS6_LXR9: MintDecimals = MintDecimals { xls: 6, lxr: 9 };
+    const XLS9_LXR6: MintDecimals = MintDecimals { xls: 9, lxr: 6 };
+    const EQUAL: MintDecimals = MintDecimals { xls: 9, lxr: 9 };
+
+    fn fixed_sale(price: u64) -> GlobalConfig {
+        GlobalConfig { swap_price: price, ..Default::default() }
+    }
+
+    #[test]
+    fn fixed_buy_equal_decimals_matches_legacy_ratio() {
+        let config = fixed_sale(DEFAULT_SWAP_PRICE);
+        assert_eq!(quote_buy(&config, 1_000_000_000, EQUAL).unwrap(), 1_000_000 * 1_000_000_000);
+    }
+
+    #[test]
+    fn fixed_buy_scales_up_when_lxr_has_more_decimals() {
+        // 1 XLS (6 decimals) = 1M LXR (9 decimals)
+        let config = fixed_sale(DEFAULT_SWAP_PRICE);
+        assert_eq!(quote_buy(&config, 1_000_000, XLS6_LXR9).unwrap(), 1_000_000 * 1_000_000_000);
+    }
+
+    #[test]
+    fn fixed_buy_scales_down_and_rounds_up_when_xls_has_more_decimals() {
+        // 1 XLS (9 decimals) = 1M LXR (6 decimals)
+        let config = fixed_sale(DEFAULT_SWAP_PRICE);
+        assert_eq!(quote_buy(&config, 1_000_000_000, XLS9_LXR6).unwrap(), 1_000_000 * 1_000_000);
+
+        // 1 atomic XLS at 1 LXR = 0.001 atomic LXR, charged as 1
+        let config = fixed_sale(1);
+        assert_eq!(quote_buy(&config, 1, XLS9_LXR6).unwrap(), 1);
+    }
+
+    #[test]
+    fn tiered_buy_crossing_boundary_with_mismatched_decimals() {
+        let mut config = GlobalConfig { sale_mode: SaleMode::Tiered, total_xls_sold: 0, ..Default::default() };
+        config.sale_tiers[0] = SaleTier { up_to: 500_000, price: 1_000_000 }; // First 0.5 XLS (6 decimals)
+        config.sale_tiers[1] = SaleTier { up_to: 0, price: 2_000_000 };
+
+        // 0.5 XLS at 1M + 0.5 XLS at 2M = 1.5M LXR (9 decimals)
+        assert_eq!(quote_buy(&config, 1_000_000, XLS6_LXR9).unwrap(), 1_500_000 * 1_000_000_000);
+    }
+
+    #[test]
+    fn stablecoin_buy_rescales_to_payment_decimals() {
+        // 1 XLS (9 decimals) at 2 USDC (6 decimals) = 2_000_000 atomic USDC
+        assert_eq!(quote_buy_with(2, 1_000_000_000, 9, 6).unwrap(), 2_000_000);
+        // 1 atomic XLS rounds up to 1 atomic USDC
+        assert_eq!(quote_buy_with(2, 1, 9, 6).unwrap(), 1);
+    }
+
+    #[test]
+    fn fixed_redemption_with_mismatched_decimals() {
+        let config = GlobalConfig { redemption_rate: DEFAULT_REDEMPTION_RATE, ..Default::default() };
+        let vault = u64::MAX;
+
+        let out = redemption_value(&config, 1_000_000, vault, 0, XLS6_LXR9).unwrap();
+        assert_eq!(out, 700_000 * 1_000_000_000);
+
+        let out = redemption_value(&config, 1_000_000_000, vault, 0, XLS9_LXR6).unwrap();
+        assert_eq!(out, 700_000 * 1_000_000);
+    }
+
+    #[test]
+    fn nav_redemption_floor_with_mismatched_decimals() {
+        let config = GlobalConfig {
+            redemption_mode: RedemptionMode::Nav,
+            redemption_floor: 500_000,
+            ..Default::default()
+        };
+        // 10 XLS circulating (6 decimals) backed by 1M LXR (9 decimals): NAV = 100k LXR per XLS, floor wins
+        let out = redemption_value(&config, 1_000_000, 1_000_000 * 1_000_000_000, 10_000_000, XLS6_LXR9).unwrap();
+        assert_eq!(out, 500_000 * 1_000_000_000);
+
+        // NAV above floor: 10 XLS backed by 10M LXR = 1M LXR per XLS
+        let out = redemption_value(&config, 1_000_000, 10_000_000 * 1_000_000_000, 10_000_000, XLS6_LXR9).unwrap();
+        assert_eq!(out, 1_000_000 * 1_000_000_000);
+    }
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+/// A Dutch auction for one tranche of XLS, escrowed out of the supply vault while it runs.
+/// Price falls linearly from start_price to end_price over `duration`; buys are only accepted inside that window.
+#[account]
+pub struct Auction {
+    pub id: u64,
+    pub start_price: u64, // LXR per XLS at start_time
+    pub end_price: u64, // LXR per XLS at start_time + duration
+    pub start_time: i64,
+    pub duration: i64,
+    pub tranche_xls: u64, // XLS moved into the Auction Vault at creation
+    pub sold_xls: u64,
+    pub lxr_raised: u64,
+    pub bump: u8,
+}
+
+impl Auction {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+}
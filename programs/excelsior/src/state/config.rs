@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 
 #[account]
+#[derive(Default)]
 pub struct GlobalConfig {
     pub admin: Pubkey,
     pub xls_mint: Pubkey,
@@ -17,12 +18,143 @@ pub struct GlobalConfig {
     // Stats
     pub total_lxr_burned: u64,
     pub total_staked_xls: u64,
-    pub acc_rewards_per_share: u128, // Precision 1e12
+    pub acc_rewards_per_share: u128, // Precision 1e12, per unit of effective stake
     pub last_inflation_timestamp: i64,
     pub lxr_vault_rewards: Pubkey, // Vault holding LXR for staking rewards
     pub bump: u8,
+
+    // Redemption Config
+    pub redemption_mode: RedemptionMode,
+    pub redemption_rate: u64, // LXR per XLS (Book Value): Fixed Mode payout, breaker liability in every mode
+    pub redemption_floor: u64, // NAV Mode: Min LXR per XLS
+    pub redemption_ceiling: u64, // NAV Mode: Max LXR per XLS (0 = No Cap)
+
+    // Swap Config
+    pub swap_price: u64, // LXR per XLS
+    pub swap_burn_bps: u16, // Share of LXR burned
+    pub swap_vault_bps: u16, // Share of LXR sent to RWA Vault
+
+    // Sale Curve
+    pub sale_mode: SaleMode,
+    pub sale_tiers: [SaleTier; MAX_SALE_TIERS],
+    pub total_xls_sold: u64, // Cumulative XLS sold from xls_vault_supply
+
+    // LXR Transfer Fee Handling
+    pub gross_up_transfer_fees: bool, // Payers on user-funded legs (buys, rent) cover the fee; vault payouts never do
+
+    pub xls_vault_staking: Pubkey, // Vault holding staked XLS
+
+    // Redemption Circuit Breaker
+    pub min_coverage_bps: u16, // Redemptions halt below this reserve coverage (0 = Disabled)
+    pub redemptions_halted: bool,
+    pub breaker_overridden: bool, // Admin pinned redemptions_halted; coverage is not enforced
+
+    // Redemption Queue
+    pub redeem_epoch: u64, // Epoch currently accepting requests
+    pub redeem_epoch_duration: i64, // Seconds an epoch stays open before the crank may settle it
+    pub redeem_lxr_reserved: u64, // LXR in the RWA Vault owed to settled, unclaimed tickets
+
+    // Locked Purchases
+    pub lock_tiers: [LockTier; MAX_LOCK_TIERS], // Discount by lock duration (None active = Disabled)
+
+    // Purchase Caps
+    pub wallet_cap_xls: u64, // Lifetime XLS per wallet (0 = No Cap)
+    pub epoch_cap_xls: u64, // XLS sold per sale epoch across all wallets (0 = No Cap)
+    pub sale_epoch_duration: i64,
+    pub sale_epoch_start: i64,
+    pub sale_epoch_sold: u64, // XLS sold since sale_epoch_start
+
+    // Referrals
+    pub referral_bps: u16, // Share of the RWA Vault leg credited to the referrer (0 = Disabled)
+    pub referral_lxr_reserved: u64, // LXR in the RWA Vault owed to referrers
+
+    pub quote_signer: Pubkey, // Ed25519 key signing OTC quotes (default = OTC Disabled)
+
+    // Dutch Auctions
+    pub next_auction_id: u64,
+    pub auction_xls_escrowed: u64, // Unsold XLS held in open Auction Vaults
+
+    // Redemption Exit Fee
+    pub redemption_fee_bps: u16, // Share of each instant redemption paid to stakers (0 = Disabled)
+    pub staker_redemption_fee_bps: u16, // Reduced fee for redeemers staking at least staker_fee_threshold_xls
+    pub staker_fee_threshold_xls: u64, // 0 = No reduced tier
+
+    // Reward Emission
+    pub reward_rate_per_second: u64, // LXR emitted to stakers per second (0 = Disabled)
+    pub last_update_ts: i64, // acc_rewards_per_share includes emissions up to here
+    pub emission_end_ts: i64,
+    pub emission_budget: u64, // Funded LXR not yet emitted
+
+    pub undistributed_rewards: u64, // LXR in the Reward Vault that arrived while nobody was staked
+
+    pub total_effective_stake: u64, // Sum of lock-boosted stakes; the denominator of acc_rewards_per_share
+
+    // Unbonding
+    pub unbonding_period: i64, // Seconds unstaked XLS waits before withdraw_unbonded (0 = Returned immediately)
+    pub total_unbonding_xls: u64, // Unstaked XLS still held in the Staking Vault
 }
 
 impl GlobalConfig {
-    pub const LEN: usize = 8 + 32*8 + 2*2 + 8*2 + 8 + 16 + 1; // Updated for new fields
+    pub const LEN: usize = 8 + 32*8 + 2*2 + 8*2 + 8 + 16 + 1
+        + 1 + 8*3 // Redemption Config
+        + 8 + 2*2 // Swap Config
+        + 1 + SaleTier::LEN * MAX_SALE_TIERS + 8 // Sale Curve
+        + 1 // LXR Transfer Fee Handling
+        + 32 // XLS Staking Vault
+        + 2 + 1 + 1 // Redemption Circuit Breaker
+        + 8 + 8 + 8 // Redemption Queue
+        + LockTier::LEN * MAX_LOCK_TIERS // Locked Purchases
+        + 8 + 8 + 8 + 8 + 8 // Purchase Caps
+        + 2 + 8 // Referrals
+        + 32 // OTC Quote Signer
+        + 8 + 8 // Dutch Auctions
+        + 2 + 2 + 8 // Redemption Exit Fee
+        + 8 + 8 + 8 + 8 // Reward Emission
+        + 8 // Undistributed Rewards
+        + 8 // Staking Locks
+        + 8 + 8; // Unbonding
+
+    /// Baseline configs predate the staking vault, so the realloc leaves it zeroed.
+    pub fn is_baseline_layout(&self) -> bool {
+        self.xls_vault_staking == Pubkey::default()
+    }
+}
+
+pub const MAX_SALE_TIERS: usize = 4;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaleMode {
+    #[default]
+    Fixed,  // Every XLS costs swap_price
+    Tiered, // Price steps up with total_xls_sold (sale_tiers)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct SaleTier {
+    pub up_to: u64, // Tier ends when total_xls_sold reaches this (Last active tier is open-ended)
+    pub price: u64, // LXR per XLS (0 = Inactive)
+}
+
+impl SaleTier {
+    pub const LEN: usize = 8 + 8;
+}
+
+pub const MAX_LOCK_TIERS: usize = 4;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct LockTier {
+    pub duration: i64, // Seconds the purchased XLS stays locked (0 = Inactive)
+    pub discount_bps: u16, // Discount on the LXR price
+    pub cliff: bool, // true = Everything unlocks at the end, false = Linear unlock
+}
+
+impl LockTier {
+    pub const LEN: usize = 8 + 2 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedemptionMode {
+    #[default]
+    Fixed, // Pays redemption_rate LXR per XLS
+    Nav,   // Pays RWA Vault Balance / Circulating XLS
 }
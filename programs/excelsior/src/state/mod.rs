@@ -2,3 +2,19 @@ pub mod config;
 pub use config::*;
 pub mod user_account;
 pub use user_account::*;
+pub mod redeem_queue;
+pub use redeem_queue::*;
+pub mod payment_mint;
+pub use payment_mint::*;
+pub mod vesting;
+pub use vesting::*;
+pub mod purchase_record;
+pub use purchase_record::*;
+pub mod referrer;
+pub use referrer::*;
+pub mod otc_nonce;
+pub use otc_nonce::*;
+pub mod auction;
+pub use auction::*;
+pub mod pool;
+pub use pool::*;
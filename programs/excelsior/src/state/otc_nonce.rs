@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+/// Marks an OTC quote as filled. Its existence is the replay guard.
+#[account]
+pub struct OtcNonce {
+    pub buyer: Pubkey,
+    pub nonce: u64,
+    pub bump: u8,
+}
+
+impl OtcNonce {
+    pub const LEN: usize = 8 + 32 + 8 + 1;
+}
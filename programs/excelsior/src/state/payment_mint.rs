@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+
+/// An accepted non-LXR payment mint for XLS purchases (e.g. USDC), keyed by the mint.
+#[account]
+pub struct PaymentMint {
+    pub mint: Pubkey,
+    pub token_program: Pubkey, // SPL Token or Token-2022, whichever owns the mint
+    pub vault: Pubkey, // Destination token account for the retained share
+    pub price: u64, // Payment-mint units per XLS (UI-unit ratio, like swap_price)
+    pub burn_bps: u16, // Share of each payment burned (0 for stablecoins)
+    pub enabled: bool,
+    pub total_received: u64, // Cumulative amount credited to the vault
+    pub bump: u8,
+}
+
+impl PaymentMint {
+    pub const LEN: usize = 8 + 32 * 3 + 8 + 2 + 1 + 8 + 1;
+}
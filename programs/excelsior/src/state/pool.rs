@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+/// The LXR/XLS constant-product pool. Reserves are tracked here rather than read from the vaults,
+/// so LXR transfer fees and direct donations never skew the curve.
+#[account]
+pub struct Pool {
+    pub lxr_reserve: u64, // LXR credited to the pool after transfer fees
+    pub xls_reserve: u64,
+    pub fee_bps: u16, // Swap fee charged on the LXR leg
+    pub protocol_fee_bps: u16, // Share of the swap fee routed to the RWA Vault; the rest stays with LPs
+    pub total_protocol_lxr: u64, // Cumulative LXR sent to the RWA Vault
+    pub bump: u8,
+}
+
+impl Pool {
+    pub const LEN: usize = 8 + 8 + 8 + 2 + 2 + 8 + 1;
+}
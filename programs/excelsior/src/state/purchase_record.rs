@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+/// Lifetime XLS a wallet has bought from the primary sale, for the per-wallet cap.
+#[account]
+pub struct PurchaseRecord {
+    pub owner: Pubkey,
+    pub total_purchased: u64,
+    pub bump: u8,
+    pub referrer: Pubkey, // Bound on the first referred purchase (default = None)
+}
+
+impl PurchaseRecord {
+    pub const LEN: usize = 8 + 32 + 8 + 1 + 32;
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+/// One settlement round of the Redemption Queue. Requests accumulate while open; the crank fixes the fill once.
+#[account]
+pub struct RedeemEpoch {
+    pub id: u64,
+    pub opened_at: i64, // Timestamp of the first request
+    pub xls_requested: u64, // XLS escrowed by all live tickets
+    pub settled: bool,
+    pub xls_filled: u64, // XLS burned at settlement
+    pub lxr_settled: u64, // LXR reserved in the RWA Vault for this epoch's tickets
+    pub bump: u8,
+}
+
+impl RedeemEpoch {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 1 + 8 + 8 + 1;
+}
+
+/// A user's pending redemption in one epoch. Repeated requests in the same epoch top it up.
+#[account]
+pub struct RedeemTicket {
+    pub owner: Pubkey,
+    pub epoch: u64,
+    pub xls_amount: u64, // XLS held in the Redeem Escrow for this ticket
+    pub bump: u8,
+}
+
+impl RedeemTicket {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+}
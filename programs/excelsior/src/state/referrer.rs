@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+/// Referral earnings of one wallet. The LXR itself stays in the RWA Vault, reserved until claimed.
+#[account]
+pub struct ReferrerAccount {
+    pub owner: Pubkey,
+    pub pending_lxr: u64,
+    pub total_claimed: u64,
+    pub referred_xls: u64, // XLS bought by referred wallets
+    pub referred_lxr: u64, // LXR paid by referred wallets
+    pub bump: u8,
+}
+
+impl ReferrerAccount {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1;
+}
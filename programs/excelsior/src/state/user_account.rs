@@ -1,13 +1,36 @@
-use anchor_lang::prelude::*;
+use anchor_lang::prelude::*;
+use crate::math::*;
 
 #[account]
+#[derive(Default)]
 pub struct UserAccount {
     pub owner: Pubkey,
     pub staked_xls: u64,
-    pub reward_debt: u128, // For MasterChef-style reward calculation
+    pub reward_debt: u128, // For MasterChef-style reward calculation, on effective_stake
     pub bump: u8,
+
+    // Fields below were appended after V1; older accounts are grown by migrate_user_account
+    pub total_claimed: u64, // Lifetime LXR settled to this staker, before transfer fees
+    pub effective_stake: u64, // staked_xls * lock_boost_bps, the staker's reward weight
+    pub lock_end: i64, // Unstaking is rejected before this (0 = Never locked)
+    pub lock_boost_bps: u16, // Reward multiplier of the current lock (10,000 = 1x)
+    pub unbonding_xls: u64, // Unstaked XLS waiting out the unbonding period; earns nothing
+    pub unbonding_end: i64, // withdraw_unbonded is allowed from here
 }
 
 impl UserAccount {
-    pub const LEN: usize = 8 + 32 + 8 + 16 + 1;
+    pub const LEN: usize = 8 + 32 + 8 + 16 + 1
+        + 8 // Claimed Rewards
+        + 8 + 8 + 2 // Staking Locks
+        + 8 + 8; // Unbonding
+
+    /// LXR earned since the last settlement: effective_stake * acc_rewards_per_share / 1e12 - reward_debt.
+    pub fn pending_rewards(&self, acc_rewards_per_share: u128) -> Result<u64> {
+        to_u64(
+            (self.effective_stake as u128)
+                .safe_mul(acc_rewards_per_share)?
+                .safe_div(REWARD_PRECISION)?
+                .safe_sub(self.reward_debt)?,
+        )
+    }
 }
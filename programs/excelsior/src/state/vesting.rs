@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use crate::math::*;
+
+/// XLS bought at a discount, released to `owner` on a linear or cliff schedule.
+/// One per user; a new locked purchase needs the previous one fully released.
+#[account]
+pub struct VestingEscrow {
+    pub owner: Pubkey,
+    pub total_xls: u64,
+    pub released_xls: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub cliff: bool,
+    pub bump: u8,
+}
+
+impl VestingEscrow {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1 + 1;
+
+    /// XLS unlocked by `now` (including what was already released).
+    pub fn vested(&self, now: i64) -> Result<u64> {
+        if now >= self.end_ts {
+            return Ok(self.total_xls);
+        }
+        if self.cliff || now <= self.start_ts {
+            return Ok(0);
+        }
+        mul_div(
+            self.total_xls,
+            now.safe_sub(self.start_ts)? as u64,
+            self.end_ts.safe_sub(self.start_ts)? as u64,
+        )
+    }
+}
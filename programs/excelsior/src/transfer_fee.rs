@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::Mint as MintState,
+};
+use anchor_spl::token_interface::Mint;
+
+/// Amounts for one `transfer_checked` of a mint that may carry a TransferFeeConfig (LXR).
+pub struct FeeAdjusted {
+    pub send: u64,     // Amount passed to transfer_checked
+    pub received: u64, // Amount credited to the destination after the withheld fee
+}
+
+/// Without gross-up the destination receives `amount - fee`.
+/// With gross-up the sender pays the fee on top, so the destination receives exactly `amount`.
+/// Mints without the extension (XLS, classic SPL Token) are fee-free.
+pub fn fee_adjusted(mint: &InterfaceAccount<Mint>, amount: u64, gross_up: bool) -> Result<FeeAdjusted> {
+    let mint_info = mint.to_account_info();
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    let fee_config = match state.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) => fee_config,
+        Err(_) => return Ok(FeeAdjusted { send: amount, received: amount }),
+    };
+    let fee = fee_config.get_epoch_fee(Clock::get()?.epoch);
+
+    if gross_up {
+        let send = fee
+            .calculate_pre_fee_amount(amount)
+            .ok_or(crate::ErrorCode::MathOverflow)?;
+        Ok(FeeAdjusted { send, received: amount })
+    } else {
+        let received = fee
+            .calculate_post_fee_amount(amount)
+            .ok_or(crate::ErrorCode::MathOverflow)?;
+        Ok(FeeAdjusted { send: amount, received })
+    }
+}
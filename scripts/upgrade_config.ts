@@ -1,8 +1,10 @@
 import * as anchor from "@coral-xyz/anchor";
 import {
     createMint,
+    createAccount,
     mintTo,
     getOrCreateAssociatedTokenAccount,
+    getAccount,
     TOKEN_2022_PROGRAM_ID
 } from "@solana/spl-token";
 import {
@@ -78,6 +80,32 @@ async function main() {
     );
     console.log("LXR Reward Vault (NEW):", lxrVaultRewards.address.toBase58());
 
+    // 4. XLS Staking Vault: its own account, since the ATA is already the Supply Vault.
+    // Staked XLS still held in the Supply Vault is moved over by upgrade_config.
+    const stakingVaultPath = "./wallets/xls_vault_staking.json";
+    let xlsVaultStaking: PublicKey;
+    if (fs.existsSync(stakingVaultPath)) {
+        xlsVaultStaking = Keypair.fromSecretKey(new Uint8Array(JSON.parse(fs.readFileSync(stakingVaultPath, 'utf8')))).publicKey;
+    } else {
+        const stakingVaultKp = Keypair.generate();
+        xlsVaultStaking = await createAccount(
+            connection, admin, xlsMint, globalConfig, stakingVaultKp, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID
+        );
+        fs.writeFileSync(stakingVaultPath, JSON.stringify(Array.from(stakingVaultKp.secretKey)));
+    }
+    console.log("XLS Staking Vault:", xlsVaultStaking.toBase58());
+
+    // Pre-flight: a baseline config (309 bytes) keeps staked XLS in the Supply Vault
+    const BASELINE_CONFIG_LEN = 8 + 32 * 7 + 2 * 2 + 8 * 2 + 8 + 16 + 32 + 1;
+    const rawConfig = await connection.getAccountInfo(globalConfig);
+    if (!rawConfig) throw new Error("Global Config not found.");
+    const baseline = rawConfig.data.length <= BASELINE_CONFIG_LEN;
+    if (baseline) {
+        const totalStaked = rawConfig.data.readBigUInt64LE(8 + 32 * 7 + 2 * 2 + 8); // total_staked_xls
+        console.log("Baseline layout: moving", totalStaked.toString(), "staked XLS from the Supply Vault");
+    } else {
+        console.log("Current layout:", rawConfig.data.length, "bytes");
+    }
 
     // Call upgrade_config
     console.log("Sending Upgrade Transaction...");
@@ -90,6 +118,9 @@ async function main() {
                 rwaVaultLxr: rwaVaultLxr.address,
                 xlsVaultSupply: xlsVaultSupply.address,
                 lxrVaultRewards: lxrVaultRewards.address,
+                xlsVaultStaking: xlsVaultStaking,
+                xlsMint: xlsMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
                 systemProgram: SystemProgram.programId,
             })
             .signers([admin])
@@ -98,6 +129,16 @@ async function main() {
         console.log("Upgrade Success! Tx:", tx);
     } catch (e) {
         console.error("Upgrade Failed:", e);
+        return;
+    }
+
+    // Post-check: the Staking Vault must back every staked and unbonding XLS
+    const config: any = await program.account.globalConfig.fetch(globalConfig);
+    const owed = BigInt(config.totalStakedXls.add(config.totalUnbondingXls).toString());
+    const held = (await getAccount(connection, xlsVaultStaking, 'confirmed', TOKEN_2022_PROGRAM_ID)).amount;
+    console.log("Staking Vault holds", held.toString(), "XLS for", owed.toString(), "staked/unbonding");
+    if (held < owed) {
+        throw new Error("Staking Vault is short of the XLS owed to stakers.");
     }
 }
 
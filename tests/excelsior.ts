@@ -13,9 +13,10 @@ const {
     createInitializeTransferFeeConfigInstruction,
     createInitializeMintInstruction,
     getMintLen,
-    amountToUiAmount
+    amountToUiAmount,
+    TOKEN_PROGRAM_ID
 } = require("@solana/spl-token");
-const { Keypair, SystemProgram, Transaction, PublicKey, SendTransactionError } = require("@solana/web3.js");
+const { Keypair, SystemProgram, Transaction, PublicKey, SendTransactionError, Ed25519Program, SYSVAR_INSTRUCTIONS_PUBKEY } = require("@solana/web3.js");
 const { assert } = require("chai");
 const { MerkleTree } = require('merkletreejs');
 const keccak256 = require('keccak256');
@@ -45,9 +46,14 @@ describe("excelsior", () => {
     let xlsVaultSupply; // Holds XLS for buying
     let xlsVaultStaking;
     let rwaVaultLxr;
+    let lxrVaultRewards;
 
     // User Accounts
     const user = Keypair.generate();
+    const [purchaseRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from("purchase_record"), user.publicKey.toBuffer()],
+        program.programId
+    );
     let userXls;
     let userLxr;
     let userAccount; // Staking State
@@ -55,6 +61,18 @@ describe("excelsior", () => {
     const DECIMALS = 9;
     const INITIAL_SUPPLY = 1_000_000 * 10 ** 9; // 1M tokens
 
+    // Asserts the transaction fails with the given program error code
+    const expectError = async (promise, code) => {
+        let error;
+        try {
+            await promise;
+        } catch (e) {
+            error = e;
+        }
+        assert.ok(error, `Should have failed with ${code}`);
+        assert.ok(error.message.includes(code), `Expected ${code}, got: ${error.message}`);
+    };
+
     before(async () => {
         // Airdrop SOL to Admin and User
         await provider.connection.confirmTransaction(
@@ -194,6 +212,17 @@ describe("excelsior", () => {
             TOKEN_2022_PROGRAM_ID
         );
 
+        // Staking Vault: a separate GlobalConfig-owned XLS account, never the supply vault
+        xlsVaultStaking = await createAccount(
+            provider.connection,
+            payer.payer,
+            xlsMint,
+            globalConfig,
+            Keypair.generate(),
+            { commitment: 'confirmed' },
+            TOKEN_2022_PROGRAM_ID
+        );
+
         // Prep RWA Vault
         rwaVaultLxr = getAssociatedTokenAddressSync(
             lxrMint,
@@ -226,9 +255,55 @@ describe("excelsior", () => {
         );
 
         const amountXls = new anchor.BN(1 * 10 ** 9); // 1 XLS
+        const maxLxrIn = new anchor.BN(1_000_000).mul(amountXls); // 1M LXR per XLS
+        const expiresAt = new anchor.BN(Math.floor(Date.now() / 1000) + 60);
+
+        // Quote tighter than the price must be rejected
+        await expectError(
+            program.methods
+                .buyXls(amountXls, maxLxrIn.subn(1), expiresAt)
+                .accounts({
+                    user: user.publicKey,
+                    globalConfig: globalConfig,
+                    userLxrAccount: userLxr,
+                    userXlsAccount: userXls,
+                    xlsVaultSupply: xlsVaultSupply,
+                    rwaVaultLxr: rwaVaultLxr,
+                    xlsMint: xlsMint,
+                    lxrMint: lxrMint,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    purchaseRecord: purchaseRecord,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([user])
+                .rpc(),
+            "MaxLxrInExceeded"
+        );
+
+        // Expired quote must be rejected
+        await expectError(
+            program.methods
+                .buyXls(amountXls, maxLxrIn, new anchor.BN(Math.floor(Date.now() / 1000) - 3600))
+                .accounts({
+                    user: user.publicKey,
+                    globalConfig: globalConfig,
+                    userLxrAccount: userLxr,
+                    userXlsAccount: userXls,
+                    xlsVaultSupply: xlsVaultSupply,
+                    rwaVaultLxr: rwaVaultLxr,
+                    xlsMint: xlsMint,
+                    lxrMint: lxrMint,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    purchaseRecord: purchaseRecord,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([user])
+                .rpc(),
+            "QuoteExpired"
+        );
 
         await program.methods
-            .buyXls(amountXls)
+            .buyXls(amountXls, maxLxrIn, expiresAt)
             .accounts({
                 user: user.publicKey,
                 globalConfig: globalConfig,
@@ -239,6 +314,8 @@ describe("excelsior", () => {
                 xlsMint: xlsMint,
                 lxrMint: lxrMint,
                 tokenProgram: TOKEN_2022_PROGRAM_ID,
+                purchaseRecord: purchaseRecord,
+                systemProgram: SystemProgram.programId,
             })
             .signers([user])
             .rpc();
@@ -265,14 +342,6 @@ describe("excelsior", () => {
             .signers([user])
             .rpc();
 
-        // Staking Vault (Reusing Supply Vault logic)
-        // See previous explanation.
-        // Actually, contract uses ctx.accounts.xls_vault_staking
-        // Unstake requires global_config authority.
-        // The Supply Vault we created is an ATA of global_config.
-        // So we can use it as Staking Vault too (Mixing funds not ideal but works for test).
-        xlsVaultStaking = xlsVaultSupply;
-
         const stakeAmount = new anchor.BN(0.5 * 10 ** 9); // 0.5 XLS
 
         await program.methods
@@ -453,7 +522,7 @@ describe("excelsior", () => {
         const lxrVaultRewardsKp = Keypair.generate(); // Standalone or ATA? 
         // Likely an ATA of GlobalConfig? Or Admin? 
         // Use an ATA of GlobalConfig (Reward Vault).
-        const lxrVaultRewards = await createAccount(
+        lxrVaultRewards = await createAccount(
             provider.connection,
             payer.payer,
             lxrMint,
@@ -471,6 +540,9 @@ describe("excelsior", () => {
                 rwaVaultLxr: rwaVaultLxr,     // Keep same
                 xlsVaultSupply: xlsVaultSupply, // Keep same
                 lxrVaultRewards: lxrVaultRewards, // NEW
+                xlsVaultStaking: xlsVaultStaking,
+                xlsMint: xlsMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
                 systemProgram: SystemProgram.programId
             })
             .signers([admin])
@@ -502,4 +574,1710 @@ describe("excelsior", () => {
         assert.equal(rwaBal.toString(), (600 * 10 ** 9).toString());
         assert.equal(rewardsBal.toString(), (400 * 10 ** 9).toString());
     });
+
+    it("Sets Redemption Params (NAV Mode)", async () => {
+        // Floor above Ceiling must be rejected
+        await expectError(
+            program.methods
+                .setRedemptionParams({ mode: { nav: {} }, rate: new anchor.BN(700_000), floor: new anchor.BN(800_000), ceiling: new anchor.BN(600_000) })
+                .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+                .signers([admin])
+                .rpc(),
+            "InvalidRedemptionParams"
+        );
+
+        // NAV Mode still needs the book value the circuit breaker measures coverage against
+        await expectError(
+            program.methods
+                .setRedemptionParams({ mode: { nav: {} }, rate: new anchor.BN(0), floor: new anchor.BN(500_000), ceiling: new anchor.BN(900_000) })
+                .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+                .signers([admin])
+                .rpc(),
+            "InvalidRedemptionParams"
+        );
+
+        await program.methods
+            .setRedemptionParams({ mode: { nav: {} }, rate: new anchor.BN(700_000), floor: new anchor.BN(500_000), ceiling: new anchor.BN(900_000) })
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+
+        const config = await program.account.globalConfig.fetch(globalConfig);
+        assert.ok(config.redemptionMode.nav !== undefined);
+        assert.equal(config.redemptionFloor.toString(), "500000");
+        assert.equal(config.redemptionCeiling.toString(), "900000");
+    });
+
+    it("Sets Swap Params (Bounds Enforced)", async () => {
+        const setSwapParams = (price, burnBps, vaultBps) => program.methods
+            .setSwapParams({ price: new anchor.BN(price), burnBps, vaultBps })
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+
+        // Burn above hard cap
+        await expectError(setSwapParams(1_000_000, 6_000, 4_000), "BurnBpsAboveCap");
+
+        // Burn + Vault != 10,000 bps
+        await expectError(setSwapParams(1_000_000, 3_000, 6_000), "InvalidSwapParams");
+
+        await setSwapParams(1_200_000, 2_500, 7_500);
+
+        const config = await program.account.globalConfig.fetch(globalConfig);
+        assert.equal(config.swapPrice.toString(), "1200000");
+        assert.equal(config.swapBurnBps, 2_500);
+        assert.equal(config.swapVaultBps, 7_500);
+
+        // Restore defaults for later tests
+        await setSwapParams(1_000_000, 3_000, 7_000);
+    });
+
+    it("Buys XLS across Sale Tiers (Exact Integration)", async () => {
+        await mintTo(
+            provider.connection,
+            admin,
+            lxrMint,
+            userLxr,
+            admin,
+            2_000_000 * 10 ** 9, // 2M LXR
+            [],
+            { commitment: 'confirmed' },
+            TOKEN_2022_PROGRAM_ID
+        );
+
+        // Tier 1 ends half an XLS from now at 1M LXR, open-ended Tier 2 at 2M LXR
+        const sold = (await program.account.globalConfig.fetch(globalConfig)).totalXlsSold;
+        const halfXls = new anchor.BN(0.5 * 10 ** 9);
+        const emptyTier = { upTo: new anchor.BN(0), price: new anchor.BN(0) };
+        const tiers = [
+            { upTo: sold.add(halfXls), price: new anchor.BN(1_000_000) },
+            { upTo: new anchor.BN(0), price: new anchor.BN(2_000_000) },
+            emptyTier,
+            emptyTier,
+        ];
+        await program.methods
+            .setSaleCurve({ mode: { tiered: {} }, tiers })
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+
+        const amountXls = new anchor.BN(1 * 10 ** 9); // 1 XLS crossing the boundary
+        const expectedCost = halfXls.muln(1_000_000).add(halfXls.muln(2_000_000)); // 1.5M LXR
+        const preLxr = (await getAccount(provider.connection, userLxr, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+
+        await program.methods
+            .buyXls(amountXls, expectedCost, new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userLxrAccount: userLxr,
+                userXlsAccount: userXls,
+                xlsVaultSupply: xlsVaultSupply,
+                rwaVaultLxr: rwaVaultLxr,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                purchaseRecord: purchaseRecord,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([user])
+            .rpc();
+
+        const postLxr = (await getAccount(provider.connection, userLxr, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        assert.equal((preLxr - postLxr).toString(), expectedCost.toString());
+
+        const config = await program.account.globalConfig.fetch(globalConfig);
+        assert.equal(config.totalXlsSold.toString(), sold.add(amountXls).toString());
+
+        // Back to Fixed pricing for later tests
+        await program.methods
+            .setSaleCurve({ mode: { fixed: {} }, tiers: [emptyTier, emptyTier, emptyTier, emptyTier] })
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+    });
+
+    it("Buys XLS with Transfer Fee Gross-Up (Vault Receives Exact Share)", async () => {
+        await mintTo(
+            provider.connection,
+            admin,
+            lxrMint,
+            userLxr,
+            admin,
+            200_000 * 10 ** 9, // 200k LXR
+            [],
+            { commitment: 'confirmed' },
+            TOKEN_2022_PROGRAM_ID
+        );
+
+        await program.methods
+            .setTransferFeeMode(true)
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+
+        const amountXls = new anchor.BN(0.1 * 10 ** 9); // 0.1 XLS = 100k LXR, 70k to Vault
+        const vaultShare = BigInt(70_000 * 10 ** 9);
+        const preVault = (await getAccount(provider.connection, rwaVaultLxr, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+
+        await program.methods
+            .buyXls(amountXls, new anchor.BN(200_000 * 10 ** 9), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userLxrAccount: userLxr,
+                userXlsAccount: userXls,
+                xlsVaultSupply: xlsVaultSupply,
+                rwaVaultLxr: rwaVaultLxr,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                purchaseRecord: purchaseRecord,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([user])
+            .rpc();
+
+        const postVault = (await getAccount(provider.connection, rwaVaultLxr, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        assert.equal((postVault - preVault).toString(), vaultShare.toString());
+
+        await program.methods
+            .setTransferFeeMode(false)
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+    });
+
+    it("Rejects substituted vaults, mints and token accounts", async () => {
+        // Rogue XLS account owned by the user, posing as a vault
+        const rogueXlsVault = await createAccount(
+            provider.connection,
+            payer.payer,
+            xlsMint,
+            user.publicKey,
+            Keypair.generate(),
+            { commitment: 'confirmed' },
+            TOKEN_2022_PROGRAM_ID
+        );
+
+        const buyAccounts = {
+            user: user.publicKey,
+            globalConfig: globalConfig,
+            userLxrAccount: userLxr,
+            userXlsAccount: userXls,
+            xlsVaultSupply: xlsVaultSupply,
+            rwaVaultLxr: rwaVaultLxr,
+            xlsMint: xlsMint,
+            lxrMint: lxrMint,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            purchaseRecord: purchaseRecord,
+            systemProgram: SystemProgram.programId,
+        };
+        const buy = (overrides) => program.methods
+            .buyXls(new anchor.BN(1000), new anchor.BN(10 ** 12), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({ ...buyAccounts, ...overrides })
+            .signers([user])
+            .rpc();
+
+        // Anchor checks fields in order, and a field's `constraint`s before its `address`
+        await expectError(buy({ xlsVaultSupply: xlsVaultStaking }), "InvalidSupplyVault");
+        await expectError(buy({ xlsVaultSupply: rogueXlsVault }), "VaultOwnerMismatch");
+        await expectError(buy({ xlsVaultSupply: rwaVaultLxr }), "VaultMintMismatch");
+        await expectError(buy({ rwaVaultLxr: lxrVaultRewards }), "InvalidRwaVault");
+        await expectError(buy({ rwaVaultLxr: userLxr }), "VaultOwnerMismatch");
+        await expectError(buy({ rwaVaultLxr: xlsVaultSupply }), "VaultMintMismatch");
+        await expectError(buy({ userXlsAccount: userLxr }), "UserXlsMintMismatch");
+        await expectError(buy({ userLxrAccount: userXls }), "UserLxrMintMismatch");
+        await expectError(buy({ userXlsAccount: xlsVaultSupply }), "UserAccountOwnerMismatch");
+        await expectError(buy({ userLxrAccount: rwaVaultLxr }), "UserAccountOwnerMismatch");
+        // A substituted mint is already caught by the user's token account bound to it
+        await expectError(buy({ xlsMint: lxrMint }), "UserXlsMintMismatch");
+        await expectError(buy({ lxrMint: xlsMint }), "UserLxrMintMismatch");
+        await expectError(buy({ tokenProgram: TOKEN_PROGRAM_ID }), "InvalidTokenProgram");
+
+        // Misconfigured vaults: right address, wrong mint / owner
+        const upgradeConfig = (overrides) => program.methods.upgradeConfig()
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                rwaVaultLxr: rwaVaultLxr,
+                xlsVaultSupply: xlsVaultSupply,
+                lxrVaultRewards: lxrVaultRewards,
+                xlsVaultStaking: xlsVaultStaking,
+                xlsMint: xlsMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                ...overrides,
+            })
+            .signers([admin])
+            .rpc();
+
+        await upgradeConfig({ xlsVaultSupply: rwaVaultLxr });
+        await expectError(buy({ xlsVaultSupply: rwaVaultLxr }), "VaultMintMismatch");
+
+        await upgradeConfig({ rwaVaultLxr: userLxr });
+        await expectError(buy({ rwaVaultLxr: userLxr }), "VaultOwnerMismatch");
+
+        await upgradeConfig({});
+
+        // Buyers could otherwise drain XLS owed to stakers
+        await expectError(upgradeConfig({ xlsVaultStaking: xlsVaultSupply }), "SharedStakingVault");
+        await expectError(upgradeConfig({ xlsVaultStaking: rwaVaultLxr }), "ConstraintTokenMint");
+        await expectError(upgradeConfig({ xlsVaultStaking: rogueXlsVault }), "ConstraintTokenOwner");
+
+        // The staking vault holds the user's stake, so it cannot be swapped for an empty one
+        const spareStakingVault = await createAccount(
+            provider.connection,
+            payer.payer,
+            xlsMint,
+            globalConfig,
+            Keypair.generate(),
+            { commitment: 'confirmed' },
+            TOKEN_2022_PROGRAM_ID
+        );
+        await expectError(upgradeConfig({ xlsVaultStaking: spareStakingVault }), "StakingVaultInUse");
+
+        // Staking vaults
+        const stake = (overrides) => program.methods
+            .stakeXls(new anchor.BN(1000))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userAccount: userAccount,
+                userXlsAccount: userXls,
+                userLxrAccount: userLxr,
+                xlsVaultStaking: xlsVaultStaking,
+                lxrVaultRewards: lxrVaultRewards,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                ...overrides,
+            })
+            .signers([user])
+            .rpc();
+
+        const unstake = (overrides) => program.methods
+            .unstakeXls(new anchor.BN(1))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userAccount: userAccount,
+                userXlsAccount: userXls,
+                userLxrAccount: userLxr,
+                xlsVaultStaking: xlsVaultStaking,
+                lxrVaultRewards: lxrVaultRewards,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                ...overrides,
+            })
+            .signers([user])
+            .rpc();
+
+        // Another wallet's user account
+        const [otherUserAccount] = PublicKey.findProgramAddressSync(
+            [Buffer.from("user_account"), payer.publicKey.toBuffer()],
+            program.programId
+        );
+        await program.methods
+            .initUser()
+            .accounts({ user: payer.publicKey, userAccount: otherUserAccount, systemProgram: SystemProgram.programId })
+            .rpc();
+
+        for (const call of [stake, unstake]) {
+            await expectError(call({ userAccount: otherUserAccount }), "ConstraintSeeds");
+            await expectError(call({ xlsVaultStaking: xlsVaultSupply }), "InvalidStakingVault");
+            await expectError(call({ xlsVaultStaking: rogueXlsVault }), "VaultOwnerMismatch");
+            await expectError(call({ xlsVaultStaking: rwaVaultLxr }), "VaultMintMismatch");
+            await expectError(call({ lxrVaultRewards: rwaVaultLxr }), "InvalidRewardVault");
+            await expectError(call({ lxrVaultRewards: userLxr }), "VaultOwnerMismatch");
+            await expectError(call({ lxrVaultRewards: xlsVaultSupply }), "VaultMintMismatch");
+            await expectError(call({ userXlsAccount: userLxr }), "UserXlsMintMismatch");
+            await expectError(call({ userLxrAccount: userXls }), "UserLxrMintMismatch");
+            await expectError(call({ userXlsAccount: xlsVaultSupply }), "UserAccountOwnerMismatch");
+            await expectError(call({ userLxrAccount: rwaVaultLxr }), "UserAccountOwnerMismatch");
+            await expectError(call({ xlsMint: lxrMint }), "UserXlsMintMismatch");
+            await expectError(call({ lxrMint: xlsMint }), "UserLxrMintMismatch");
+            await expectError(call({ tokenProgram: TOKEN_PROGRAM_ID }), "InvalidTokenProgram");
+        }
+
+        // Redemptions
+        const redeem = (overrides) => program.methods
+            .redeemXls(new anchor.BN(1000), new anchor.BN(0), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userAccount: userAccount,
+                userLxrAccount: userLxr,
+                userXlsAccount: userXls,
+                xlsVaultSupply: xlsVaultSupply,
+                rwaVaultLxr: rwaVaultLxr,
+                lxrVaultRewards: lxrVaultRewards,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                ...overrides,
+            })
+            .signers([user])
+            .rpc();
+
+        await expectError(redeem({ userAccount: otherUserAccount }), "ConstraintSeeds");
+        await expectError(redeem({ xlsVaultSupply: xlsVaultStaking }), "InvalidSupplyVault");
+        await expectError(redeem({ xlsVaultSupply: rogueXlsVault }), "VaultOwnerMismatch");
+        await expectError(redeem({ xlsVaultSupply: rwaVaultLxr }), "VaultMintMismatch");
+        await expectError(redeem({ rwaVaultLxr: lxrVaultRewards }), "InvalidRwaVault");
+        await expectError(redeem({ rwaVaultLxr: userLxr }), "VaultOwnerMismatch");
+        await expectError(redeem({ rwaVaultLxr: xlsVaultSupply }), "VaultMintMismatch");
+        await expectError(redeem({ lxrVaultRewards: rwaVaultLxr }), "InvalidRewardVault");
+        await expectError(redeem({ lxrVaultRewards: xlsVaultSupply }), "VaultMintMismatch");
+        await expectError(redeem({ userXlsAccount: userLxr }), "UserXlsMintMismatch");
+        await expectError(redeem({ userLxrAccount: userXls }), "UserLxrMintMismatch");
+        await expectError(redeem({ userXlsAccount: xlsVaultSupply }), "UserAccountOwnerMismatch");
+        await expectError(redeem({ userLxrAccount: rwaVaultLxr }), "UserAccountOwnerMismatch");
+        await expectError(redeem({ xlsMint: lxrMint }), "UserXlsMintMismatch");
+        await expectError(redeem({ lxrMint: xlsMint }), "UserLxrMintMismatch");
+        await expectError(redeem({ tokenProgram: TOKEN_PROGRAM_ID }), "InvalidTokenProgram");
+    });
+
+    it("Returns typed errors for zero amounts", async () => {
+        await expectError(
+            program.methods
+                .buyXls(new anchor.BN(0), new anchor.BN(0), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+                .accounts({
+                    user: user.publicKey,
+                    globalConfig: globalConfig,
+                    userLxrAccount: userLxr,
+                    userXlsAccount: userXls,
+                    xlsVaultSupply: xlsVaultSupply,
+                    rwaVaultLxr: rwaVaultLxr,
+                    xlsMint: xlsMint,
+                    lxrMint: lxrMint,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    purchaseRecord: purchaseRecord,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([user])
+                .rpc(),
+            "ZeroAmount"
+        );
+    });
+
+    it("Halts redemptions via the circuit breaker", async () => {
+        const breakerAccounts = {
+            admin: admin.publicKey,
+            globalConfig: globalConfig,
+            xlsMint: xlsMint,
+            lxrMint: lxrMint,
+            xlsVaultSupply: xlsVaultSupply,
+            rwaVaultLxr: rwaVaultLxr,
+        };
+        const setBreaker = (minCoverageBps, overrideHalted) => program.methods
+            .setRedemptionBreaker({ minCoverageBps, overrideHalted })
+            .accounts(breakerAccounts)
+            .signers([admin])
+            .rpc();
+
+        // Admin pins the breaker: redemptions halt and the crank cannot reset it
+        await setBreaker(10_000, true);
+        let config = await program.account.globalConfig.fetch(globalConfig);
+        assert.ok(config.redemptionsHalted);
+        assert.ok(config.breakerOverridden);
+
+        await expectError(
+            program.methods
+                .redeemXls(new anchor.BN(1), new anchor.BN(0), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+                .accounts({
+                    user: user.publicKey,
+                    globalConfig: globalConfig,
+                    userLxrAccount: userLxr,
+                    userXlsAccount: userXls,
+                    xlsVaultSupply: xlsVaultSupply,
+                    rwaVaultLxr: rwaVaultLxr,
+                    lxrVaultRewards: lxrVaultRewards,
+                    xlsMint: xlsMint,
+                    lxrMint: lxrMint,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                })
+                .signers([user])
+                .rpc(),
+            "RedemptionsHalted"
+        );
+
+        const { admin: _admin, ...pokeAccounts } = breakerAccounts;
+        await expectError(
+            program.methods.pokeRedemptionBreaker().accounts(pokeAccounts).rpc(),
+            "BreakerOverridden"
+        );
+
+        // Releasing the override with the breaker disabled resumes redemptions
+        await setBreaker(0, null);
+        config = await program.account.globalConfig.fetch(globalConfig);
+        assert.ok(!config.redemptionsHalted);
+        assert.ok(!config.breakerOverridden);
+    });
+
+    it("Queues, cancels, settles and claims redemptions", async () => {
+        const [redeemEscrow] = PublicKey.findProgramAddressSync([Buffer.from("redeem_escrow")], program.programId);
+        const epochPda = (id) => PublicKey.findProgramAddressSync(
+            [Buffer.from("redeem_epoch"), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+            program.programId
+        )[0];
+        const ticketPda = (epoch) => PublicKey.findProgramAddressSync(
+            [Buffer.from("redeem_ticket"), epoch.toBuffer(), user.publicKey.toBuffer()],
+            program.programId
+        )[0];
+        const xlsBalance = async () => (await getAccount(provider.connection, userXls, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+
+        const epochId = (await program.account.globalConfig.fetch(globalConfig)).redeemEpoch.toNumber();
+        const redeemEpoch = epochPda(epochId);
+        const redeemTicket = ticketPda(redeemEpoch);
+        const ticketAccounts = {
+            user: user.publicKey,
+            globalConfig: globalConfig,
+            redeemEpoch: redeemEpoch,
+            redeemTicket: redeemTicket,
+            userXlsAccount: userXls,
+            xlsRedeemEscrow: redeemEscrow,
+            xlsMint: xlsMint,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+        };
+        const request = (amount) => program.methods
+            .requestRedeem(new anchor.BN(amount))
+            .accounts({ ...ticketAccounts, systemProgram: SystemProgram.programId })
+            .signers([user])
+            .rpc();
+
+        // Cancel returns the escrowed XLS in full
+        const before = await xlsBalance();
+        await request(1000);
+        await program.methods.cancelRedeem().accounts(ticketAccounts).signers([user]).rpc();
+        assert.equal((await xlsBalance()).toString(), before.toString());
+
+        // Permissionless settlement must wait for the epoch to end
+        await request(1000);
+        const settleAccounts = {
+            globalConfig: globalConfig,
+            redeemEpoch: redeemEpoch,
+            xlsRedeemEscrow: redeemEscrow,
+            xlsVaultSupply: xlsVaultSupply,
+            rwaVaultLxr: rwaVaultLxr,
+            xlsMint: xlsMint,
+            lxrMint: lxrMint,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+        };
+        await expectError(
+            program.methods.settleRedeemEpoch().accounts({ ...settleAccounts, cranker: user.publicKey }).signers([user]).rpc(),
+            "RedeemEpochNotReady"
+        );
+        await program.methods.settleRedeemEpoch().accounts({ ...settleAccounts, cranker: admin.publicKey }).signers([admin]).rpc();
+
+        const epoch = await program.account.redeemEpoch.fetch(redeemEpoch);
+        assert.ok(epoch.settled);
+        assert.equal(epoch.xlsRequested.toString(), "1000");
+        let config = await program.account.globalConfig.fetch(globalConfig);
+        assert.equal(config.redeemEpoch.toNumber(), epochId + 1);
+        assert.equal(config.redeemLxrReserved.toString(), epoch.lxrSettled.toString());
+
+        await expectError(
+            program.methods.cancelRedeem().accounts(ticketAccounts).signers([user]).rpc(),
+            "RedeemEpochSettled"
+        );
+
+        await program.methods
+            .claimRedeem()
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                redeemEpoch: redeemEpoch,
+                redeemTicket: redeemTicket,
+                userAccount: null,
+                userLxrAccount: userLxr,
+                userXlsAccount: userXls,
+                xlsRedeemEscrow: redeemEscrow,
+                rwaVaultLxr: rwaVaultLxr,
+                lxrVaultRewards: lxrVaultRewards,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([user])
+            .rpc();
+
+        config = await program.account.globalConfig.fetch(globalConfig);
+        assert.equal(config.redeemLxrReserved.toString(), "0");
+        assert.isNull(await provider.connection.getAccountInfo(redeemTicket));
+    });
+
+    it("Buys XLS with a registered SPL stablecoin", async () => {
+        // Stand-in USDC: classic SPL Token, 6 decimals
+        const usdcMint = await createMint(
+            provider.connection, payer.payer, admin.publicKey, null, 6, undefined, { commitment: 'confirmed' }, TOKEN_PROGRAM_ID
+        );
+        const userUsdc = await createAccount(
+            provider.connection, payer.payer, usdcMint, user.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_PROGRAM_ID
+        );
+        const treasuryUsdc = await createAccount(
+            provider.connection, payer.payer, usdcMint, rwaWallet, undefined, { commitment: 'confirmed' }, TOKEN_PROGRAM_ID
+        );
+        await mintTo(provider.connection, admin, usdcMint, userUsdc, admin, 100 * 10 ** 6, [], { commitment: 'confirmed' }, TOKEN_PROGRAM_ID);
+
+        const [paymentConfig] = PublicKey.findProgramAddressSync(
+            [Buffer.from("payment_mint"), usdcMint.toBuffer()],
+            program.programId
+        );
+        const setPaymentMint = (enabled) => program.methods
+            .setPaymentMint({ price: new anchor.BN(2), burnBps: 0, enabled })
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                paymentConfig: paymentConfig,
+                paymentMint: usdcMint,
+                paymentVault: treasuryUsdc,
+                paymentTokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
+
+        const buyWith = (amountXls, maxIn) => program.methods
+            .buyXlsWith(new anchor.BN(amountXls), new anchor.BN(maxIn), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                paymentConfig: paymentConfig,
+                userPaymentAccount: userUsdc,
+                userXlsAccount: userXls,
+                paymentVault: treasuryUsdc,
+                xlsVaultSupply: xlsVaultSupply,
+                xlsMint: xlsMint,
+                paymentMint: usdcMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                paymentTokenProgram: TOKEN_PROGRAM_ID,
+                purchaseRecord: purchaseRecord,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([user])
+            .rpc();
+
+        await setPaymentMint(false);
+        await expectError(buyWith(10 ** 9, 2 * 10 ** 6), "PaymentMintDisabled");
+
+        await setPaymentMint(true);
+        await expectError(buyWith(10 ** 9, 2 * 10 ** 6 - 1), "MaxPaymentInExceeded");
+
+        const xlsBefore = (await getAccount(provider.connection, userXls, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        await buyWith(10 ** 9, 2 * 10 ** 6); // 1 XLS at 2 USDC
+
+        const treasuryBal = (await getAccount(provider.connection, treasuryUsdc, "confirmed", TOKEN_PROGRAM_ID)).amount;
+        const xlsAfter = (await getAccount(provider.connection, userXls, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        assert.equal(treasuryBal.toString(), (2 * 10 ** 6).toString());
+        assert.equal((xlsAfter - xlsBefore).toString(), (10 ** 9).toString());
+
+        const registry = await program.account.paymentMint.fetch(paymentConfig);
+        assert.equal(registry.totalReceived.toString(), (2 * 10 ** 6).toString());
+    });
+
+    it("Buys discounted XLS into a cliff-vesting escrow", async () => {
+        const inactive = { duration: new anchor.BN(0), discountBps: 0, cliff: false };
+        const setLockTiers = (tiers) => program.methods
+            .setLockTiers({ tiers })
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+
+        // Longer locks must not discount less
+        await expectError(setLockTiers([
+            { duration: new anchor.BN(3600), discountBps: 1000, cliff: false },
+            { duration: new anchor.BN(7200), discountBps: 500, cliff: true },
+            inactive, inactive,
+        ]), "InvalidLockTiers");
+
+        await setLockTiers([
+            { duration: new anchor.BN(3600), discountBps: 1000, cliff: false },
+            { duration: new anchor.BN(86_400), discountBps: 2000, cliff: true },
+            inactive, inactive,
+        ]);
+
+        const [vestingEscrow] = PublicKey.findProgramAddressSync(
+            [Buffer.from("vesting"), user.publicKey.toBuffer()],
+            program.programId
+        );
+        const [vestingVault] = PublicKey.findProgramAddressSync(
+            [Buffer.from("vesting_vault"), vestingEscrow.toBuffer()],
+            program.programId
+        );
+        const buyLocked = (tierIndex) => program.methods
+            .buyXlsLocked(new anchor.BN(1000), tierIndex, new anchor.BN("18446744073709551615"), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                vestingEscrow: vestingEscrow,
+                purchaseRecord: purchaseRecord,
+                vestingVault: vestingVault,
+                userLxrAccount: userLxr,
+                xlsVaultSupply: xlsVaultSupply,
+                rwaVaultLxr: rwaVaultLxr,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([user])
+            .rpc();
+
+        await expectError(buyLocked(2), "InvalidLockTier");
+        await buyLocked(1);
+
+        const escrow = await program.account.vestingEscrow.fetch(vestingEscrow);
+        assert.equal(escrow.totalXls.toString(), "1000");
+        assert.ok(escrow.cliff);
+        assert.equal(escrow.endTs.sub(escrow.startTs).toNumber(), 86_400);
+        const vaultBal = (await getAccount(provider.connection, vestingVault, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        assert.equal(vaultBal.toString(), "1000");
+
+        await expectError(buyLocked(0), "VestingActive");
+        await expectError(
+            program.methods
+                .releaseVested()
+                .accounts({
+                    user: user.publicKey,
+                    globalConfig: globalConfig,
+                    vestingEscrow: vestingEscrow,
+                    vestingVault: vestingVault,
+                    userXlsAccount: userXls,
+                    xlsMint: xlsMint,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                })
+                .signers([user])
+                .rpc(),
+            "NothingVested"
+        );
+    });
+
+    it("Enforces per-wallet and per-epoch purchase caps", async () => {
+        const setCaps = (walletCap, epochCap, duration) => program.methods
+            .setPurchaseCaps({ walletCapXls: new anchor.BN(walletCap), epochCapXls: new anchor.BN(epochCap), epochDuration: new anchor.BN(duration) })
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+        const buy = (amount) => program.methods
+            .buyXls(new anchor.BN(amount), new anchor.BN("18446744073709551615"), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                purchaseRecord: purchaseRecord,
+                userLxrAccount: userLxr,
+                userXlsAccount: userXls,
+                xlsVaultSupply: xlsVaultSupply,
+                rwaVaultLxr: rwaVaultLxr,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([user])
+            .rpc();
+
+        // An epoch cap without a window is meaningless
+        await expectError(setCaps(0, 1000, 0), "InvalidPurchaseCaps");
+
+        const purchased = (await program.account.purchaseRecord.fetch(purchaseRecord)).totalPurchased.toNumber();
+
+        await setCaps(0, 1000, 3600);
+        await buy(600);
+        await expectError(buy(401), "EpochCapExceeded");
+
+        await setCaps(purchased + 1000, 0, 0);
+        await expectError(buy(401), "WalletCapExceeded");
+        await buy(400);
+
+        const record = await program.account.purchaseRecord.fetch(purchaseRecord);
+        assert.equal(record.totalPurchased.toNumber(), purchased + 1000);
+
+        await setCaps(0, 0, 0);
+    });
+
+    it("Credits and pays referral rewards", async () => {
+        await expectError(
+            program.methods.setReferralParams(2001).accounts({ admin: admin.publicKey, globalConfig: globalConfig }).signers([admin]).rpc(),
+            "InvalidReferralParams"
+        );
+        await program.methods.setReferralParams(1000).accounts({ admin: admin.publicKey, globalConfig: globalConfig }).signers([admin]).rpc();
+
+        const referrerOf = (wallet) => PublicKey.findProgramAddressSync([Buffer.from("referrer"), wallet.toBuffer()], program.programId)[0];
+        const recordOf = (wallet) => PublicKey.findProgramAddressSync([Buffer.from("purchase_record"), wallet.toBuffer()], program.programId)[0];
+        for (const wallet of [admin, user]) {
+            await program.methods
+                .registerReferrer()
+                .accounts({ user: wallet.publicKey, referrerAccount: referrerOf(wallet.publicKey), systemProgram: SystemProgram.programId })
+                .signers([wallet])
+                .rpc();
+        }
+
+        // Admin doubles as a second buyer
+        const adminLxr = await createAccount(provider.connection, payer.payer, lxrMint, admin.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        const adminXls = await createAccount(provider.connection, payer.payer, xlsMint, admin.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        await mintTo(provider.connection, admin, lxrMint, adminLxr, admin, 100_000 * 10 ** 9, [], { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+
+        const buyReferred = (buyer, buyerLxr, buyerXls, referrer) => program.methods
+            .buyXls(new anchor.BN(1000), new anchor.BN("18446744073709551615"), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({
+                user: buyer.publicKey,
+                globalConfig: globalConfig,
+                purchaseRecord: recordOf(buyer.publicKey),
+                userLxrAccount: buyerLxr,
+                userXlsAccount: buyerXls,
+                xlsVaultSupply: xlsVaultSupply,
+                rwaVaultLxr: rwaVaultLxr,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                referrerAccount: referrerOf(referrer),
+                referrerRecord: recordOf(referrer),
+                buyerReferrerAccount: referrerOf(buyer.publicKey),
+            })
+            .signers([buyer])
+            .rpc();
+
+        await expectError(buyReferred(user, userLxr, userXls, user.publicKey), "SelfReferral");
+
+        await buyReferred(user, userLxr, userXls, admin.publicKey);
+        const adminReferrer = await program.account.referrerAccount.fetch(referrerOf(admin.publicKey));
+        assert.ok(adminReferrer.pendingLxr.gtn(0));
+        assert.equal(adminReferrer.referredXls.toString(), "1000");
+        const userRecord = await program.account.purchaseRecord.fetch(purchaseRecord);
+        assert.ok(userRecord.referrer.equals(admin.publicKey));
+
+        // user was referred by admin, so admin cannot be referred by user
+        await expectError(buyReferred(admin, adminLxr, adminXls, user.publicKey), "CircularReferral");
+
+        // Nor can a longer loop close: admin -> user -> payer -> admin
+        const third = payer.payer;
+        await program.methods
+            .registerReferrer()
+            .accounts({ user: third.publicKey, referrerAccount: referrerOf(third.publicKey), systemProgram: SystemProgram.programId })
+            .signers([third])
+            .rpc();
+        const thirdLxr = await createAccount(provider.connection, payer.payer, lxrMint, third.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        const thirdXls = await createAccount(provider.connection, payer.payer, xlsMint, third.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        await mintTo(provider.connection, admin, lxrMint, thirdLxr, admin, 100_000 * 10 ** 9, [], { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        await buyReferred(third, thirdLxr, thirdXls, user.publicKey);
+        await expectError(buyReferred(admin, adminLxr, adminXls, third.publicKey), "BuyerHasReferrals");
+        await expectError(
+            program.methods
+                .buyXls(new anchor.BN(1000), new anchor.BN("18446744073709551615"), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+                .accounts({
+                    user: admin.publicKey,
+                    globalConfig: globalConfig,
+                    purchaseRecord: recordOf(admin.publicKey),
+                    userLxrAccount: adminLxr,
+                    userXlsAccount: adminXls,
+                    xlsVaultSupply: xlsVaultSupply,
+                    rwaVaultLxr: rwaVaultLxr,
+                    xlsMint: xlsMint,
+                    lxrMint: lxrMint,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    referrerAccount: referrerOf(third.publicKey),
+                    referrerRecord: recordOf(third.publicKey),
+                    buyerReferrerAccount: referrerOf(user.publicKey), // Not the buyer's own
+                })
+                .signers([admin])
+                .rpc(),
+            "InvalidBuyerReferrer"
+        );
+
+        const preLxr = (await getAccount(provider.connection, adminLxr, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        await program.methods
+            .claimReferral()
+            .accounts({
+                user: admin.publicKey,
+                globalConfig: globalConfig,
+                referrerAccount: referrerOf(admin.publicKey),
+                userLxrAccount: adminLxr,
+                rwaVaultLxr: rwaVaultLxr,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([admin])
+            .rpc();
+        const postLxr = (await getAccount(provider.connection, adminLxr, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        assert.ok(postLxr > preLxr);
+
+        const claimed = await program.account.referrerAccount.fetch(referrerOf(admin.publicKey));
+        assert.equal(claimed.pendingLxr.toString(), "0");
+        assert.equal(claimed.totalClaimed.toString(), adminReferrer.pendingLxr.toString());
+    });
+
+    it("Fills admin-signed OTC quotes once", async () => {
+        const quoteSigner = Keypair.generate();
+        const le64 = (n) => new anchor.BN(n).toArrayLike(Buffer, "le", 8);
+        const amountXls = 5000;
+        const price = 400_000; // Below the list price
+        const expiresAt = Math.floor(Date.now() / 1000) + 60;
+
+        const buyOtc = (nonce, signer, domain = Buffer.concat([program.programId.toBuffer(), globalConfig.toBuffer(), Buffer.from("excelsior-otc-v1")])) => {
+            const message = Buffer.concat([
+                domain,
+                user.publicKey.toBuffer(),
+                le64(amountXls),
+                le64(price),
+                le64(nonce),
+                le64(expiresAt),
+            ]);
+            const [otcNonce] = PublicKey.findProgramAddressSync(
+                [Buffer.from("otc_nonce"), user.publicKey.toBuffer(), le64(nonce)],
+                program.programId
+            );
+            return program.methods
+                .buyXlsOtc(new anchor.BN(amountXls), new anchor.BN(price), new anchor.BN(nonce), new anchor.BN(expiresAt))
+                .accounts({
+                    user: user.publicKey,
+                    globalConfig: globalConfig,
+                    purchaseRecord: purchaseRecord,
+                    otcNonce: otcNonce,
+                    userLxrAccount: userLxr,
+                    userXlsAccount: userXls,
+                    xlsVaultSupply: xlsVaultSupply,
+                    rwaVaultLxr: rwaVaultLxr,
+                    xlsMint: xlsMint,
+                    lxrMint: lxrMint,
+                    instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .preInstructions([Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message })])
+                .signers([user])
+                .rpc();
+        };
+        const setQuoteSigner = (key) => program.methods
+            .setQuoteSigner(key)
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+
+        await expectError(buyOtc(1, quoteSigner), "OtcDisabled");
+        await setQuoteSigner(quoteSigner.publicKey);
+
+        // Valid signature, wrong key
+        await expectError(buyOtc(1, Keypair.generate()), "InvalidOtcSignature");
+        // Right key, but signed without the program / config domain
+        await expectError(buyOtc(1, quoteSigner, Buffer.alloc(0)), "InvalidOtcSignature");
+
+        const preXls = (await getAccount(provider.connection, userXls, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        await buyOtc(1, quoteSigner);
+        const postXls = (await getAccount(provider.connection, userXls, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        assert.equal((postXls - preXls).toString(), amountXls.toString());
+
+        // The nonce PDA already exists, so the same quote cannot be filled twice
+        await expectError(buyOtc(1, quoteSigner), "already in use");
+
+        await setQuoteSigner(PublicKey.default);
+    });
+
+    it("Runs a Dutch auction tranche and returns the remainder", async () => {
+        const config = await program.account.globalConfig.fetch(globalConfig);
+        const [auction] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction"), config.nextAuctionId.toArrayLike(Buffer, "le", 8)],
+            program.programId
+        );
+        const [auctionVault] = PublicKey.findProgramAddressSync(
+            [Buffer.from("auction_vault"), auction.toBuffer()],
+            program.programId
+        );
+        const supplyBalance = async () => (await getAccount(provider.connection, xlsVaultSupply, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        const tranche = 10_000n;
+
+        const preSupply = await supplyBalance();
+        await program.methods
+            .createAuction({
+                startPrice: new anchor.BN(2_000_000),
+                endPrice: new anchor.BN(1_000_000),
+                startTime: new anchor.BN(Math.floor(Date.now() / 1000) - 10),
+                duration: new anchor.BN(3600),
+                trancheXls: new anchor.BN(tranche.toString()),
+            })
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                auction: auction,
+                auctionVault: auctionVault,
+                xlsVaultSupply: xlsVaultSupply,
+                xlsMint: xlsMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
+        assert.equal((preSupply - (await supplyBalance())).toString(), tranche.toString());
+
+        const buyAuction = (amount, expiresAt = Math.floor(Date.now() / 1000) + 60) => program.methods
+            .buyXlsAuction(new anchor.BN(amount.toString()), new anchor.BN("18446744073709551615"), new anchor.BN(expiresAt))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                auction: auction,
+                auctionVault: auctionVault,
+                purchaseRecord: purchaseRecord,
+                userLxrAccount: userLxr,
+                userXlsAccount: userXls,
+                rwaVaultLxr: rwaVaultLxr,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([user])
+            .rpc();
+
+        const preXls = (await getAccount(provider.connection, userXls, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        await buyAuction(1_000n);
+        const postXls = (await getAccount(provider.connection, userXls, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        assert.equal((postXls - preXls).toString(), "1000");
+
+        const state = await program.account.auction.fetch(auction);
+        assert.equal(state.soldXls.toString(), "1000");
+        await expectError(buyAuction(tranche), "VaultInsufficient");
+        await expectError(buyAuction(1_000n, Math.floor(Date.now() / 1000) - 60), "QuoteExpired");
+
+        // Closing before the scheduled end cancels the tranche (AuctionClosedEarly)
+
+        await program.methods
+            .closeAuction()
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                auction: auction,
+                auctionVault: auctionVault,
+                xlsVaultSupply: xlsVaultSupply,
+                xlsMint: xlsMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([admin])
+            .rpc();
+
+        // Only the sold 1,000 XLS left the supply
+        assert.equal((preSupply - (await supplyBalance())).toString(), "1000");
+        assert.isNull(await provider.connection.getAccountInfo(auction));
+        assert.isNull(await provider.connection.getAccountInfo(auctionVault));
+    });
+
+    it("Provides liquidity and swaps through the LXR/XLS pool", async () => {
+        const pda = (seed) => PublicKey.findProgramAddressSync([Buffer.from(seed)], program.programId)[0];
+        const pool = pda("pool");
+        const poolLxrVault = pda("pool_lxr_vault");
+        const poolXlsVault = pda("pool_xls_vault");
+        const lpMint = pda("lp_mint");
+        const balance = async (account) => (await getAccount(provider.connection, account, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+
+        await program.methods
+            .initPool({ feeBps: 30, protocolFeeBps: 5000 })
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                pool: pool,
+                poolLxrVault: poolLxrVault,
+                poolXlsVault: poolXlsVault,
+                lpMint: lpMint,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
+
+        const userLp = await createAccount(
+            provider.connection,
+            payer.payer,
+            lpMint,
+            user.publicKey,
+            undefined,
+            { commitment: 'confirmed' },
+            TOKEN_2022_PROGRAM_ID
+        );
+        const liquidityAccounts = {
+            user: user.publicKey,
+            globalConfig: globalConfig,
+            pool: pool,
+            poolLxrVault: poolLxrVault,
+            poolXlsVault: poolXlsVault,
+            lpMint: lpMint,
+            userLxrAccount: userLxr,
+            userXlsAccount: userXls,
+            userLpAccount: userLp,
+            xlsMint: xlsMint,
+            lxrMint: lxrMint,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+        };
+
+        await program.methods
+            .addLiquidity(new anchor.BN(10_000_000), new anchor.BN(100_000), new anchor.BN(1))
+            .accounts(liquidityAccounts)
+            .signers([user])
+            .rpc();
+        const lpBalance = await balance(userLp);
+        assert.ok(lpBalance > 0n);
+
+        // Reserves are what the vaults were credited, net of the LXR transfer fee
+        let poolState = await program.account.pool.fetch(pool);
+        assert.equal(poolState.lxrReserve.toString(), (await balance(poolLxrVault)).toString());
+        assert.equal(poolState.xlsReserve.toString(), "100000");
+
+        const swap = (amountIn, minOut, direction) => program.methods
+            .swapPool(new anchor.BN(amountIn), new anchor.BN(minOut), direction)
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                pool: pool,
+                poolLxrVault: poolLxrVault,
+                poolXlsVault: poolXlsVault,
+                userLxrAccount: userLxr,
+                userXlsAccount: userXls,
+                rwaVaultLxr: rwaVaultLxr,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([user])
+            .rpc();
+
+        await expectError(swap(1_000_000, 100_000, { lxrToXls: {} }), "MinAmountOutNotMet");
+
+        const preRwa = await balance(rwaVaultLxr);
+        const preXls = await balance(userXls);
+        await swap(1_000_000, 1, { lxrToXls: {} });
+        assert.ok((await balance(userXls)) > preXls);
+        assert.ok((await balance(rwaVaultLxr)) > preRwa, "Protocol share of the fee reaches the RWA Vault");
+
+        const preLxr = await balance(userLxr);
+        await swap(5_000, 1, { xlsToLxr: {} });
+        assert.ok((await balance(userLxr)) > preLxr);
+
+        poolState = await program.account.pool.fetch(pool);
+        assert.ok(poolState.totalProtocolLxr.toNumber() > 0);
+
+        await program.methods
+            .removeLiquidity(new anchor.BN(lpBalance.toString()), new anchor.BN(1), new anchor.BN(1))
+            .accounts(liquidityAccounts)
+            .signers([user])
+            .rpc();
+        assert.equal((await balance(userLp)).toString(), "0");
+        poolState = await program.account.pool.fetch(pool);
+        assert.equal(poolState.xlsReserve.toString(), "0");
+    });
+
+    it("Routes the redemption exit fee to stakers", async () => {
+        const balance = async (account) => (await getAccount(provider.connection, account, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        const setFee = (feeBps, stakerFeeBps, threshold) => program.methods
+            .setRedemptionFee({ feeBps, stakerFeeBps, stakerThresholdXls: new anchor.BN(threshold) })
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+        const redeem = (stakerAccount) => program.methods
+            .redeemXls(new anchor.BN(1_000_000), new anchor.BN(0), new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userAccount: stakerAccount,
+                userLxrAccount: userLxr,
+                userXlsAccount: userXls,
+                xlsVaultSupply: xlsVaultSupply,
+                rwaVaultLxr: rwaVaultLxr,
+                lxrVaultRewards: lxrVaultRewards,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([user])
+            .rpc();
+
+        await expectError(setFee(200, 300, 0), "InvalidRedemptionFee");
+
+        // Base tier: the user's stake is below the threshold
+        await setFee(100, 50, "18446744073709551615");
+        const preAcc = (await program.account.globalConfig.fetch(globalConfig)).accRewardsPerShare;
+        let preRewards = await balance(lxrVaultRewards);
+        await redeem(null);
+        const baseFee = (await balance(lxrVaultRewards)) - preRewards;
+        assert.ok(baseFee > 0n);
+        const postAcc = (await program.account.globalConfig.fetch(globalConfig)).accRewardsPerShare;
+        assert.ok(postAcc.gt(preAcc), "Stakers accrue the exit fee");
+
+        // Reduced tier once the stake reaches the threshold
+        await setFee(100, 50, 1);
+        preRewards = await balance(lxrVaultRewards);
+        await redeem(userAccount);
+        const stakerFee = (await balance(lxrVaultRewards)) - preRewards;
+        assert.ok(stakerFee > 0n && stakerFee < baseFee);
+
+        await setFee(0, 0, 0);
+    });
+
+    it("Charges the exit fee on queued redemptions", async () => {
+        const balance = async (account) => (await getAccount(provider.connection, account, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        const [redeemEscrow] = PublicKey.findProgramAddressSync([Buffer.from("redeem_escrow")], program.programId);
+        const setFee = (feeBps, stakerFeeBps, threshold) => program.methods
+            .setRedemptionFee({ feeBps, stakerFeeBps, stakerThresholdXls: new anchor.BN(threshold) })
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+
+        // Queue, settle and claim one ticket, returning what the user and the stakers received
+        const queueAndClaim = async (stakerAccount) => {
+            const epochId = (await program.account.globalConfig.fetch(globalConfig)).redeemEpoch.toNumber();
+            const redeemEpoch = PublicKey.findProgramAddressSync(
+                [Buffer.from("redeem_epoch"), new anchor.BN(epochId).toArrayLike(Buffer, "le", 8)],
+                program.programId
+            )[0];
+            const redeemTicket = PublicKey.findProgramAddressSync(
+                [Buffer.from("redeem_ticket"), redeemEpoch.toBuffer(), user.publicKey.toBuffer()],
+                program.programId
+            )[0];
+            await program.methods
+                .requestRedeem(new anchor.BN(1_000_000))
+                .accounts({
+                    user: user.publicKey,
+                    globalConfig: globalConfig,
+                    redeemEpoch: redeemEpoch,
+                    redeemTicket: redeemTicket,
+                    userXlsAccount: userXls,
+                    xlsRedeemEscrow: redeemEscrow,
+                    xlsMint: xlsMint,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([user])
+                .rpc();
+            await program.methods
+                .settleRedeemEpoch()
+                .accounts({
+                    cranker: admin.publicKey,
+                    globalConfig: globalConfig,
+                    redeemEpoch: redeemEpoch,
+                    xlsRedeemEscrow: redeemEscrow,
+                    xlsVaultSupply: xlsVaultSupply,
+                    rwaVaultLxr: rwaVaultLxr,
+                    xlsMint: xlsMint,
+                    lxrMint: lxrMint,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                })
+                .signers([admin])
+                .rpc();
+
+            const preLxr = await balance(userLxr);
+            const preRewards = await balance(lxrVaultRewards);
+            await program.methods
+                .claimRedeem()
+                .accounts({
+                    user: user.publicKey,
+                    globalConfig: globalConfig,
+                    redeemEpoch: redeemEpoch,
+                    redeemTicket: redeemTicket,
+                    userAccount: stakerAccount,
+                    userLxrAccount: userLxr,
+                    userXlsAccount: userXls,
+                    xlsRedeemEscrow: redeemEscrow,
+                    rwaVaultLxr: rwaVaultLxr,
+                    lxrVaultRewards: lxrVaultRewards,
+                    xlsMint: xlsMint,
+                    lxrMint: lxrMint,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                })
+                .signers([user])
+                .rpc();
+            return {
+                settled: BigInt((await program.account.redeemEpoch.fetch(redeemEpoch)).lxrSettled.toString()),
+                received: (await balance(userLxr)) - preLxr,
+                fee: (await balance(lxrVaultRewards)) - preRewards,
+            };
+        };
+
+        // Queueing is no way around the base exit fee
+        await setFee(100, 50, "18446744073709551615");
+        const base = await queueAndClaim(null);
+        assert.ok(base.fee > 0n, "Stakers receive the exit fee");
+        assert.ok(base.received < base.settled, "The user is paid net of the exit fee");
+
+        // ...and stakers above the threshold get the same reduced tier
+        await setFee(100, 50, 1);
+        const staker = await queueAndClaim(userAccount);
+        assert.ok(staker.fee > 0n && staker.fee < base.fee);
+
+        await setFee(0, 0, 0);
+        const config = await program.account.globalConfig.fetch(globalConfig);
+        assert.equal(config.redeemLxrReserved.toString(), "0");
+    });
+
+    it("Returns authoritative quotes from view instructions", async () => {
+        const balance = async (account) => (await getAccount(provider.connection, account, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        const quoteAccounts = {
+            globalConfig: globalConfig,
+            xlsMint: xlsMint,
+            lxrMint: lxrMint,
+            xlsVaultSupply: xlsVaultSupply,
+            rwaVaultLxr: rwaVaultLxr,
+        };
+
+        // The buy quote matches what the buy actually costs
+        const quote = await program.methods.quoteBuy(new anchor.BN(2_000)).accounts(quoteAccounts).view();
+        const preLxr = await balance(userLxr);
+        await program.methods
+            .buyXls(new anchor.BN(2_000), quote.lxrPaid, new anchor.BN(Math.floor(Date.now() / 1000) + 60))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                purchaseRecord: purchaseRecord,
+                userLxrAccount: userLxr,
+                userXlsAccount: userXls,
+                xlsVaultSupply: xlsVaultSupply,
+                rwaVaultLxr: rwaVaultLxr,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([user])
+            .rpc();
+        assert.equal((preLxr - (await balance(userLxr))).toString(), quote.lxrPaid.toString());
+
+        const redeemQuote = await program.methods
+            .quoteRedeem(new anchor.BN(1_000_000))
+            .accounts({ ...quoteAccounts, userAccount: userAccount })
+            .view();
+        assert.ok(redeemQuote.lxrValue.gte(redeemQuote.lxrOut));
+
+        const nav = await program.methods.getNav().accounts(quoteAccounts).view();
+        assert.ok(nav.circulatingXls.gtn(0));
+        assert.ok(nav.navPerXls.gtn(0));
+
+        const pending = await program.methods
+            .pendingRewards(user.publicKey)
+            .accounts({ globalConfig: globalConfig, userAccount: userAccount })
+            .view();
+        const config = await program.account.globalConfig.fetch(globalConfig);
+        const userState = await program.account.userAccount.fetch(userAccount);
+        const expected = userState.stakedXls.mul(config.accRewardsPerShare).div(new anchor.BN("1000000000000")).sub(userState.rewardDebt);
+        assert.equal(pending.toString(), expected.toString());
+    });
+
+    it("Accrues staking rewards from a funded per-second emission", async () => {
+        const adminLxr = await createAccount(provider.connection, payer.payer, lxrMint, admin.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        await mintTo(provider.connection, admin, lxrMint, adminLxr, admin, 1_000_000, [], { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+
+        const setEmission = (ratePerSecond, fundAmount) => program.methods
+            .setEmissionSchedule({ ratePerSecond: new anchor.BN(ratePerSecond), fundAmount: new anchor.BN(fundAmount) })
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                adminLxrAccount: adminLxr,
+                lxrVaultRewards: lxrVaultRewards,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([admin])
+            .rpc();
+        const pendingRewards = () => program.methods
+            .pendingRewards(user.publicKey)
+            .accounts({ globalConfig: globalConfig, userAccount: userAccount })
+            .view();
+
+        await setEmission(1_000, 1_000_000);
+        let config = await program.account.globalConfig.fetch(globalConfig);
+        assert.equal(config.rewardRatePerSecond.toString(), "1000");
+        assert.ok(config.emissionBudget.gtn(0));
+        assert.ok(config.emissionEndTs.gt(config.lastUpdateTs));
+
+        const before = await pendingRewards();
+        await new Promise((resolve) => setTimeout(resolve, 2_000));
+        const after = await pendingRewards();
+        assert.ok(after.gt(before), "Pending rewards grow between updates");
+
+        // Staking zero settles the accumulator lazily
+        const accBefore = config.accRewardsPerShare;
+        await program.methods
+            .stakeXls(new anchor.BN(0))
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userAccount: userAccount,
+                userXlsAccount: userXls,
+                userLxrAccount: userLxr,
+                xlsVaultStaking: xlsVaultStaking,
+                lxrVaultRewards: lxrVaultRewards,
+                xlsMint: xlsMint,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([user])
+            .rpc();
+        config = await program.account.globalConfig.fetch(globalConfig);
+        assert.ok(config.accRewardsPerShare.gt(accBefore));
+        assert.ok(config.emissionBudget.ltn(1_000_000));
+
+        await setEmission(0, 0);
+        config = await program.account.globalConfig.fetch(globalConfig);
+        assert.equal(config.rewardRatePerSecond.toString(), "0");
+    });
+
+    it("Claims staking rewards to an alternative destination", async () => {
+        const adminLxr = await createAccount(provider.connection, payer.payer, lxrMint, admin.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        await mintTo(provider.connection, admin, lxrMint, adminLxr, admin, 1_000_000, [], { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        const setEmission = (ratePerSecond, fundAmount) => program.methods
+            .setEmissionSchedule({ ratePerSecond: new anchor.BN(ratePerSecond), fundAmount: new anchor.BN(fundAmount) })
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                adminLxrAccount: adminLxr,
+                lxrVaultRewards: lxrVaultRewards,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([admin])
+            .rpc();
+        const destination = await createAccount(provider.connection, payer.payer, lxrMint, Keypair.generate().publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        const claim = (dest) => program.methods
+            .claimRewards()
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userAccount: userAccount,
+                userLxrAccount: userLxr,
+                destination: dest,
+                lxrVaultRewards: lxrVaultRewards,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([user])
+            .rpc();
+
+        // Accrue, then stop the emission so the pending amount is fixed
+        await setEmission(1_000, 1_000_000);
+        await new Promise((resolve) => setTimeout(resolve, 2_000));
+        await setEmission(0, 0);
+
+        await expectError(claim(xlsVaultSupply), "UserLxrMintMismatch");
+
+        const pending = await program.methods
+            .pendingRewards(user.publicKey)
+            .accounts({ globalConfig: globalConfig, userAccount: userAccount })
+            .view();
+        assert.ok(pending.gtn(0));
+        const before = (await program.account.userAccount.fetch(userAccount)).totalClaimed;
+
+        await claim(destination);
+        const received = (await getAccount(provider.connection, destination, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        assert.ok(received > 0n, "Rewards land in the alternative destination");
+        const after = (await program.account.userAccount.fetch(userAccount)).totalClaimed;
+        assert.equal(after.sub(before).toString(), pending.toString());
+
+        await expectError(claim(null), "NoStakingRewards");
+    });
+
+    it("Parks rent rewards while nobody is staked", async () => {
+        const adminLxr = await createAccount(provider.connection, payer.payer, lxrMint, admin.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        await mintTo(provider.connection, admin, lxrMint, adminLxr, admin, 1_000_000, [], { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        const stakeAccounts = {
+            user: user.publicKey,
+            globalConfig: globalConfig,
+            userAccount: userAccount,
+            userXlsAccount: userXls,
+            userLxrAccount: userLxr,
+            xlsVaultStaking: xlsVaultStaking,
+            lxrVaultRewards: lxrVaultRewards,
+            xlsMint: xlsMint,
+            lxrMint: lxrMint,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+        };
+        const sweep = (amount) => program.methods
+            .sweepUndistributedRewards(new anchor.BN(amount))
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                lxrVaultRewards: lxrVaultRewards,
+                destination: rwaVaultLxr,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([admin])
+            .rpc();
+
+        const staked = (await program.account.userAccount.fetch(userAccount)).stakedXls;
+        await program.methods.unstakeXls(staked).accounts(stakeAccounts).signers([user]).rpc();
+
+        await program.methods.distributeRent(new anchor.BN(1_000_000))
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                adminLxrAccount: adminLxr,
+                rwaVaultLxr: rwaVaultLxr,
+                lxrVaultRewards: lxrVaultRewards,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([admin])
+            .rpc();
+        let config = await program.account.globalConfig.fetch(globalConfig);
+        const parked = config.undistributedRewards;
+        assert.ok(parked.gtn(0), "The staker share is parked, not stranded");
+
+        // The admin may redirect part of it
+        await expectError(sweep(parked.addn(1)), "InsufficientFunds");
+        await sweep(parked.divn(2));
+        config = await program.account.globalConfig.fetch(globalConfig);
+        const remaining = parked.sub(parked.divn(2));
+        assert.equal(config.undistributedRewards.toString(), remaining.toString());
+
+        // The first stake releases the rest to stakers
+        await program.methods.stakeXls(staked).accounts(stakeAccounts).signers([user]).rpc();
+        config = await program.account.globalConfig.fetch(globalConfig);
+        assert.equal(config.undistributedRewards.toString(), "0");
+        const pending = await program.methods
+            .pendingRewards(user.publicKey)
+            .accounts({ globalConfig: globalConfig, userAccount: userAccount })
+            .view();
+        assert.ok(pending.gtn(0) && pending.lte(remaining), "The returning staker earns the parked rewards");
+    });
+
+    it("Boosts locked stakes and rejects early unstakes", async () => {
+        // A fresh staker, so the lock does not hold up the shared user's stake
+        const staker = Keypair.generate();
+        await provider.connection.confirmTransaction(
+            await provider.connection.requestAirdrop(staker.publicKey, 2 * 10 ** 9),
+            "confirmed"
+        );
+        const stakerXls = await createAccount(provider.connection, payer.payer, xlsMint, staker.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        const stakerLxr = await createAccount(provider.connection, payer.payer, lxrMint, staker.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        await mintTo(provider.connection, admin, xlsMint, stakerXls, admin, 1_000_000, [], { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        const [stakerAccount] = PublicKey.findProgramAddressSync(
+            [Buffer.from("user_account"), staker.publicKey.toBuffer()],
+            program.programId
+        );
+        await program.methods.initUser()
+            .accounts({ user: staker.publicKey, userAccount: stakerAccount, systemProgram: SystemProgram.programId })
+            .signers([staker])
+            .rpc();
+
+        const stakeAccounts = {
+            user: staker.publicKey,
+            globalConfig: globalConfig,
+            userAccount: stakerAccount,
+            userXlsAccount: stakerXls,
+            userLxrAccount: stakerLxr,
+            xlsVaultStaking: xlsVaultStaking,
+            lxrVaultRewards: lxrVaultRewards,
+            xlsMint: xlsMint,
+            lxrMint: lxrMint,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+        };
+        const lock = (period) => program.methods
+            .lockStake(period)
+            .accounts({
+                user: staker.publicKey,
+                globalConfig: globalConfig,
+                userAccount: stakerAccount,
+                userLxrAccount: stakerLxr,
+                lxrVaultRewards: lxrVaultRewards,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([staker])
+            .rpc();
+
+        await program.methods.stakeXls(new anchor.BN(1_000_000)).accounts(stakeAccounts).signers([staker]).rpc();
+        const before = await program.account.globalConfig.fetch(globalConfig);
+
+        await lock({ sixMonths: {} });
+        const state = await program.account.userAccount.fetch(stakerAccount);
+        assert.equal(state.effectiveStake.toString(), "1500000");
+        assert.equal(state.lockBoostBps, 15_000);
+        const after = await program.account.globalConfig.fetch(globalConfig);
+        assert.equal(after.totalEffectiveStake.sub(before.totalEffectiveStake).toString(), "500000");
+        assert.equal(after.totalStakedXls.toString(), before.totalStakedXls.toString());
+
+        await expectError(
+            program.methods.unstakeXls(new anchor.BN(1)).accounts(stakeAccounts).signers([staker]).rpc(),
+            "StakeLocked"
+        );
+        await expectError(lock({ threeMonths: {} }), "LockShortened");
+
+        // Extending keeps the stake locked and raises the boost
+        await lock({ twelveMonths: {} });
+        const extended = await program.account.userAccount.fetch(stakerAccount);
+        assert.ok(extended.lockEnd.gt(state.lockEnd));
+        assert.equal(extended.effectiveStake.toString(), "2000000");
+
+        // Anyone may crank an expired lock back to 1x, but not an active one
+        const expire = (owner, account, lxrAccount) => program.methods
+            .expireLock(owner)
+            .accounts({
+                globalConfig: globalConfig,
+                userAccount: account,
+                userLxrAccount: lxrAccount,
+                lxrVaultRewards: lxrVaultRewards,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .rpc();
+        await expectError(expire(staker.publicKey, stakerAccount, stakerLxr), "LockNotExpired");
+        await expectError(expire(user.publicKey, userAccount, userLxr), "LockNotExpired");
+    });
+
+    it("Holds unstaked XLS for the unbonding period", async () => {
+        const balance = async (account) => (await getAccount(provider.connection, account, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        const setPeriod = (seconds) => program.methods
+            .setUnbondingPeriod(new anchor.BN(seconds))
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+        const stakeAccounts = {
+            user: user.publicKey,
+            globalConfig: globalConfig,
+            userAccount: userAccount,
+            userXlsAccount: userXls,
+            userLxrAccount: userLxr,
+            xlsVaultStaking: xlsVaultStaking,
+            lxrVaultRewards: lxrVaultRewards,
+            xlsMint: xlsMint,
+            lxrMint: lxrMint,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+        };
+        const withdraw = () => program.methods
+            .withdrawUnbonded()
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userAccount: userAccount,
+                userXlsAccount: userXls,
+                xlsVaultStaking: xlsVaultStaking,
+                xlsMint: xlsMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([user])
+            .rpc();
+        const cancel = () => program.methods
+            .cancelUnbonding()
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userAccount: userAccount,
+                userLxrAccount: userLxr,
+                lxrVaultRewards: lxrVaultRewards,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([user])
+            .rpc();
+
+        const unstake = (amount) => program.methods.unstakeXls(new anchor.BN(amount)).accounts(stakeAccounts).signers([user]).rpc();
+
+        await expectError(setPeriod(31 * 86_400), "InvalidUnbondingPeriod");
+        await setPeriod(3_600);
+
+        // Unstaking stops earning at once but keeps the XLS in the vault
+        const staked = (await program.account.userAccount.fetch(userAccount)).stakedXls;
+        const xlsBefore = await balance(userXls);
+        await unstake(1_000);
+        let state = await program.account.userAccount.fetch(userAccount);
+        assert.equal(state.unbondingXls.toString(), "1000");
+        assert.equal(staked.sub(state.stakedXls).toString(), "1000");
+        assert.equal((await balance(userXls)).toString(), xlsBefore.toString());
+        assert.equal((await program.account.globalConfig.fetch(globalConfig)).totalUnbondingXls.toString(), "1000");
+        await expectError(withdraw(), "StillUnbonding");
+
+        // Cancelling restakes the whole unbonding balance
+        await cancel();
+        state = await program.account.userAccount.fetch(userAccount);
+        assert.equal(state.unbondingXls.toString(), "0");
+        assert.equal(state.stakedXls.toString(), staked.toString());
+        await expectError(withdraw(), "NothingUnbonding");
+
+        // A second unstake cannot push back the first one's release
+        await setPeriod(1);
+        await unstake(1_000);
+        const firstEnd = (await program.account.userAccount.fetch(userAccount)).unbondingEnd;
+        await expectError(unstake(500), "AlreadyUnbonding");
+        state = await program.account.userAccount.fetch(userAccount);
+        assert.equal(state.unbondingXls.toString(), "1000");
+        assert.equal(state.unbondingEnd.toString(), firstEnd.toString());
+
+        // Once the period has passed the XLS is released
+        await new Promise((resolve) => setTimeout(resolve, 2_000));
+        await withdraw();
+        assert.equal(((await balance(userXls)) - xlsBefore).toString(), "1000");
+        assert.equal((await program.account.globalConfig.fetch(globalConfig)).totalUnbondingXls.toString(), "0");
+
+        // ...after which the next unstake may start unbonding
+        await unstake(500);
+        assert.equal((await program.account.userAccount.fetch(userAccount)).unbondingXls.toString(), "500");
+        await cancel();
+
+        await setPeriod(0);
+    });
+
+    it("Only migrates user accounts with the pre-upgrade layout", async () => {
+        await expectError(
+            program.methods
+                .migrateUserAccount(user.publicKey)
+                .accounts({ payer: payer.publicKey, userAccount: userAccount, systemProgram: SystemProgram.programId })
+                .rpc(),
+            "UserAccountCurrent"
+        );
+        const state = await program.account.userAccount.fetch(userAccount);
+        assert.equal(state.lockBoostBps, 10_000);
+    });
+
+    it("Keeps the Reward Vault solvent when gross-up is on", async () => {
+        const balance = async (account) => (await getAccount(provider.connection, account, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+        const setGrossUp = (grossUp) => program.methods
+            .setTransferFeeMode(grossUp)
+            .accounts({ admin: admin.publicKey, globalConfig: globalConfig })
+            .signers([admin])
+            .rpc();
+        const adminLxr = await createAccount(provider.connection, payer.payer, lxrMint, admin.publicKey, undefined, { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+        await mintTo(provider.connection, admin, lxrMint, adminLxr, admin, 10_000_000, [], { commitment: 'confirmed' }, TOKEN_2022_PROGRAM_ID);
+
+        await setGrossUp(true);
+        await program.methods.distributeRent(new anchor.BN(1_000_000))
+            .accounts({
+                admin: admin.publicKey,
+                globalConfig: globalConfig,
+                adminLxrAccount: adminLxr,
+                rwaVaultLxr: rwaVaultLxr,
+                lxrVaultRewards: lxrVaultRewards,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([admin])
+            .rpc();
+
+        const pending = await program.methods
+            .pendingRewards(user.publicKey)
+            .accounts({ globalConfig: globalConfig, userAccount: userAccount })
+            .view();
+        assert.ok(pending.gtn(0));
+        const preVault = await balance(lxrVaultRewards);
+        const preUser = await balance(userLxr);
+        await program.methods
+            .claimRewards()
+            .accounts({
+                user: user.publicKey,
+                globalConfig: globalConfig,
+                userAccount: userAccount,
+                userLxrAccount: userLxr,
+                destination: null,
+                lxrVaultRewards: lxrVaultRewards,
+                lxrMint: lxrMint,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([user])
+            .rpc();
+
+        // The vault pays out exactly what the accumulator owes; the claimer bears the transfer fee
+        assert.equal((preVault - (await balance(lxrVaultRewards))).toString(), pending.toString());
+        assert.ok((await balance(userLxr)) - preUser < BigInt(pending.toString()));
+
+        await setGrossUp(false);
+    });
 });